@@ -0,0 +1,324 @@
+use prelude::*;
+use core::{Layer, Sprite, Color};
+
+/// Determines what happens when an animation reaches its last frame.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PlaybackMode {
+    /// Restart from the first frame.
+    Loop,
+    /// Play backwards to the first frame, then forward again.
+    PingPong,
+    /// Stop on the last frame.
+    Once,
+}
+
+/// A sequence of sprite frames with individual frame durations.
+///
+/// Animations describe which frames of a [`Sprite`](struct.Sprite.html) to show for how long. They
+/// are played back by an [`AnimationPlayer`](struct.AnimationPlayer.html).
+#[derive(Clone)]
+pub struct Animation {
+    frames  : Vec<(u32, f32)>,
+    mode    : PlaybackMode,
+}
+
+impl Animation {
+
+    /// Creates an animation showing each frame from first_frame to last_frame (inclusive) for
+    /// frame_duration seconds. If first_frame is larger than last_frame, the frames are played
+    /// in reverse.
+    pub fn new(first_frame: u32, last_frame: u32, frame_duration: f32, mode: PlaybackMode) -> Animation {
+        let frames = frame_ids(first_frame, last_frame).into_iter().map(|frame_id| (frame_id, frame_duration)).collect();
+        Animation {
+            frames  : frames,
+            mode    : mode,
+        }
+    }
+
+    /// Creates an animation from a list of frame ids and their durations in seconds.
+    pub fn from_frames(frames: &[(u32, f32)], mode: PlaybackMode) -> Animation {
+        Animation {
+            frames  : frames.to_vec(),
+            mode    : mode,
+        }
+    }
+
+    /// Creates an animation from a frame range defined in the sprite's sprite sheet metadata.
+    /// Frames without a duration in the metadata are shown for default_duration seconds.
+    /// Returns None if the sprite has no frame range with given name.
+    pub fn from_sprite(sprite: &Sprite, name: &str, default_duration: f32, mode: PlaybackMode) -> Option<Animation> {
+        sprite.frame_range(name).map(|(first_frame, last_frame)| {
+            let frames = frame_ids(first_frame, last_frame).into_iter().map(|frame_id| {
+                (frame_id, sprite.frame_duration(frame_id).unwrap_or(default_duration))
+            }).collect();
+            Animation {
+                frames  : frames,
+                mode    : mode,
+            }
+        })
+    }
+
+    /// Returns a copy of the animation using given playback mode.
+    pub fn with_mode(self: &Self, mode: PlaybackMode) -> Animation {
+        Animation {
+            frames  : self.frames.clone(),
+            mode    : mode,
+        }
+    }
+
+    /// Returns a copy of the animation with all frame durations set to given value.
+    pub fn with_frame_duration(self: &Self, frame_duration: f32) -> Animation {
+        Animation {
+            frames  : self.frames.iter().map(|&(frame_id, _)| (frame_id, frame_duration)).collect(),
+            mode    : self.mode,
+        }
+    }
+
+    /// Returns the playback mode of the animation.
+    pub fn mode(self: &Self) -> PlaybackMode {
+        self.mode
+    }
+
+    /// Returns the number of frames of the animation.
+    pub fn num_frames(self: &Self) -> u32 {
+        self.frames.len() as u32
+    }
+
+    /// Returns the time in seconds required to play each frame once.
+    pub fn duration(self: &Self) -> f32 {
+        self.frames.iter().fold(0.0, |sum, &(_, duration)| sum + duration)
+    }
+
+    /// Returns the number of steps in one playback cycle.
+    fn cycle_len(self: &Self) -> usize {
+        let num_frames = self.frames.len();
+        if self.mode == PlaybackMode::PingPong && num_frames > 2 { num_frames * 2 - 2 } else { num_frames }
+    }
+
+    /// Returns frame id and duration of given step of the playback cycle.
+    fn step(self: &Self, position: usize) -> (u32, f32) {
+        let num_frames = self.frames.len();
+        if position < num_frames {
+            self.frames[position]
+        } else {
+            self.frames[num_frames * 2 - 2 - position]
+        }
+    }
+}
+
+/// Plays named [`Animation`](struct.Animation.html)s and tracks the current frame.
+///
+/// Advance the player each frame using [`AnimationPlayer::update()`](#method.update), e.g. with the
+/// frame delta provided by [`utils::mainloop()`](utils/fn.mainloop.html), and draw the current
+/// frame with [`AnimationPlayer::draw()`](#method.draw).
+pub struct AnimationPlayer {
+    clips       : HashMap<String, Animation>,
+    current     : Option<String>,
+    position    : usize,
+    elapsed     : f32,
+    speed       : f32,
+    playing     : bool,
+    finished    : bool,
+    on_complete : Option<Box<dyn FnMut(&str) + Send>>,
+}
+
+impl AnimationPlayer {
+
+    /// Creates a new animation player without any clips.
+    pub fn new() -> AnimationPlayer {
+        AnimationPlayer {
+            clips       : HashMap::new(),
+            current     : None,
+            position    : 0,
+            elapsed     : 0.0,
+            speed       : 1.0,
+            playing     : false,
+            finished    : false,
+            on_complete : None,
+        }
+    }
+
+    /// Creates a new animation player with a looping clip for each frame range defined in the
+    /// sprite's sprite sheet metadata. Frames without a duration in the metadata are shown for
+    /// default_duration seconds.
+    pub fn from_sprite(sprite: &Sprite, default_duration: f32) -> AnimationPlayer {
+        let mut player = Self::new();
+        for name in sprite.frame_range_names() {
+            if let Some(animation) = Animation::from_sprite(sprite, &name, default_duration, PlaybackMode::Loop) {
+                player.clips.insert(name, animation);
+            }
+        }
+        player
+    }
+
+    /// Adds a clip to the player, replacing any existing clip of the same name.
+    pub fn add(self: &mut Self, name: &str, animation: Animation) -> &mut Self {
+        self.clips.insert(name.to_string(), animation);
+        if self.current.as_ref().map_or(false, |current| current == name) {
+            self.rewind();
+        }
+        self
+    }
+
+    /// Returns the clip with given name.
+    pub fn clip(self: &Self, name: &str) -> Option<&Animation> {
+        self.clips.get(name)
+    }
+
+    /// Starts playing the named clip from its first frame. Does nothing if the clip is already
+    /// the current clip, so this can safely be called every frame. Use
+    /// [`AnimationPlayer::restart()`](#method.restart) to replay it. Returns false if no such clip exists.
+    pub fn play(self: &mut Self, name: &str) -> bool {
+        if !self.clips.contains_key(name) {
+            return false;
+        }
+        if self.current.as_ref().map_or(true, |current| current != name) {
+            self.current = Some(name.to_string());
+            self.rewind();
+            self.playing = true;
+        }
+        true
+    }
+
+    /// Restarts the current clip from its first frame.
+    pub fn restart(self: &mut Self) -> &mut Self {
+        self.rewind();
+        self.playing = self.current.is_some();
+        self
+    }
+
+    /// Pauses playback on the current frame.
+    pub fn pause(self: &mut Self) -> &mut Self {
+        self.playing = false;
+        self
+    }
+
+    /// Resumes paused playback.
+    pub fn resume(self: &mut Self) -> &mut Self {
+        self.playing = self.current.is_some() && !self.finished;
+        self
+    }
+
+    /// Sets the playback speed. 1.0 plays at the speed defined by the frame durations, 2.0 twice as fast.
+    pub fn set_speed(self: &mut Self, speed: f32) -> &mut Self {
+        self.speed = speed.max(0.0);
+        self
+    }
+
+    /// Returns the playback speed.
+    pub fn speed(self: &Self) -> f32 {
+        self.speed
+    }
+
+    /// Sets a callback that is invoked with the clip name whenever a clip completes. Looping
+    /// clips complete at the end of each cycle, clips played once when they reach their last frame.
+    pub fn on_complete<F>(self: &mut Self, callback: F) -> &mut Self where F: FnMut(&str) + Send + 'static {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns the name of the current clip.
+    pub fn current(self: &Self) -> Option<&str> {
+        self.current.as_ref().map(|current| current.as_str())
+    }
+
+    /// Returns whether the player is currently advancing frames.
+    pub fn is_playing(self: &Self) -> bool {
+        self.playing
+    }
+
+    /// Returns whether a clip played once has reached its end.
+    pub fn is_finished(self: &Self) -> bool {
+        self.finished
+    }
+
+    /// Returns the sprite frame id of the current frame.
+    pub fn frame_id(self: &Self) -> u32 {
+        match self.current_clip() {
+            Some(clip) if clip.frames.len() > 0 => clip.step(self.position).0,
+            _ => 0,
+        }
+    }
+
+    /// Advances playback by given number of seconds.
+    pub fn update(self: &mut Self, delta: f32) -> &mut Self {
+
+        if !self.playing {
+            return self;
+        }
+
+        let (cycle_len, cycle_duration, mode) = match self.current_clip() {
+            Some(clip) => (clip.cycle_len(), clip.duration(), clip.mode),
+            None => return self,
+        };
+
+        if cycle_len == 0 || cycle_duration <= 0.0 {
+            return self;
+        }
+
+        self.elapsed += delta * self.speed;
+
+        loop {
+            let duration = self.current_clip().unwrap().step(self.position).1;
+
+            if self.elapsed < duration {
+                break;
+            }
+
+            self.elapsed -= duration;
+            self.position += 1;
+
+            if self.position >= cycle_len {
+                if mode == PlaybackMode::Once {
+                    self.position = cycle_len - 1;
+                    self.elapsed = 0.0;
+                    self.playing = false;
+                    self.finished = true;
+                } else {
+                    self.position = 0;
+                }
+                if let (Some(current), Some(callback)) = (self.current.as_ref(), self.on_complete.as_mut()) {
+                    callback(current);
+                }
+                if self.finished {
+                    break;
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Draws the current frame of given sprite onto the given layer.
+    pub fn draw(self: &Self, sprite: &Sprite, layer: &Layer, x: f32, y: f32, color: Color) -> &Self {
+        sprite.draw(layer, self.frame_id(), x, y, color);
+        self
+    }
+
+    /// Draws the current frame of given sprite onto the given layer and applies given color, rotation and scaling.
+    pub fn draw_transformed(self: &Self, sprite: &Sprite, layer: &Layer, x: f32, y: f32, color: Color, rotation: f32, scale_x: f32, scale_y: f32) -> &Self {
+        sprite.draw_transformed(layer, self.frame_id(), x, y, color, rotation, scale_x, scale_y);
+        self
+    }
+
+    /// Returns the current clip.
+    fn current_clip(self: &Self) -> Option<&Animation> {
+        self.current.as_ref().and_then(|current| self.clips.get(current))
+    }
+
+    /// Moves playback to the first frame of the current clip.
+    fn rewind(self: &mut Self) {
+        self.position = 0;
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+}
+
+/// Returns the frame ids from first to last (inclusive), in reverse if first is larger than last.
+fn frame_ids(first_frame: u32, last_frame: u32) -> Vec<u32> {
+    if first_frame <= last_frame {
+        (first_frame..last_frame + 1).collect()
+    } else {
+        (last_frame..first_frame + 1).rev().collect()
+    }
+}
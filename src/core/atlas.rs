@@ -0,0 +1,347 @@
+use prelude::*;
+use glium;
+use core::{display, Display, Rect, RenderContextTexture, Error};
+use std::borrow::Cow;
+
+// Initial page size. Pages grow up to the maximum supported size when a frame doesn't fit.
+pub const INITIAL_PAGE_SIZE: u32 = 1024;
+
+// Upper bound for the page size, regardless of the supported texture size. Limits the memory
+// used by each page to 64 MiB.
+pub const MAX_PAGE_SIZE: u32 = 4096;
+
+// Upper bound for the memory used by all pages together, e.g. 64 pages of 1024x1024 pixels.
+pub const MAX_ATLAS_BYTES: u64 = 256 * 1024 * 1024;
+
+// Limits guaranteed by OpenGL 3.0, used when the actual limits cannot be queried.
+const MIN_TEXTURE_SIZE: u32 = 1024;
+const MIN_ARRAY_TEXTURE_LAYERS: u32 = 256;
+
+const GL_MAX_TEXTURE_SIZE: u32 = 0x0D33;
+const GL_MAX_ARRAY_TEXTURE_LAYERS: u32 = 0x88FF;
+
+// Border around each frame, filled with the frame's edge pixels to prevent bleeding.
+const PADDING: u32 = 1;
+
+/// Location of a single frame within the atlas. The uv rect is given in pixels.
+#[derive(Copy, Clone)]
+pub struct AtlasFrame {
+    pub page    : u32,
+    pub uv      : Rect,
+}
+
+/// A row of frames within a page.
+#[derive(Clone)]
+struct Shelf {
+    y       : u32,
+    height  : u32,
+    width   : u32,
+}
+
+/// A single layer of the atlas texture array.
+struct Page {
+    data    : Vec<(u8, u8, u8, u8)>,
+    shelves : Vec<Shelf>,
+    height  : u32,
+    /// Number of frames stored on the page that were not released yet.
+    frames  : u32,
+    /// Whether the page was modified since it was last uploaded.
+    dirty   : bool,
+}
+
+impl Page {
+    fn new(size: u32) -> Self {
+        Page {
+            data    : vec![(0, 0, 0, 0); size as usize * size as usize],
+            shelves : Vec::new(),
+            height  : 0,
+            frames  : 0,
+            dirty   : true,
+        }
+    }
+}
+
+/// Allocation state of the atlas, used to roll back partially inserted sprites.
+struct Checkpoint {
+    num_pages   : usize,
+    pages       : Vec<(Vec<Shelf>, u32, u32)>,
+}
+
+/// Packs sprite frames into a texture array of large, square pages.
+///
+/// Frames are packed into shelves. All pages share the same size, which is increased when a
+/// frame larger than the current page size is inserted or the maximum number of pages is
+/// reached. Since frame locations are stored in pixels, growing the pages does not invalidate
+/// previously returned locations.
+///
+/// A copy of each page is kept in memory to rebuild the texture array when pages are added,
+/// removed or grown. Otherwise, only modified pages are uploaded. Pages are limited to
+/// `MAX_PAGE_SIZE` and all pages together to `MAX_ATLAS_BYTES`, frames exceeding this are
+/// rejected with `Error::AtlasFull`.
+///
+/// Released frames only free their space once all frames on the same page were released.
+pub struct Atlas {
+    page_size   : u32,
+    max_size    : u32,
+    max_pages   : u32,
+    pages       : Vec<Page>,
+    /// Whether the number or size of the pages changed since the texture array was created.
+    resized     : bool,
+    pub texture : glium::texture::SrgbTexture2dArray,
+}
+
+impl Atlas {
+
+    /// Creates a new, empty atlas.
+    pub fn new(display: &Display) -> Self {
+        let max_size = cmp::min(gl_limit(display, GL_MAX_TEXTURE_SIZE).unwrap_or(MIN_TEXTURE_SIZE), MAX_PAGE_SIZE);
+        Atlas {
+            page_size   : cmp::min(INITIAL_PAGE_SIZE, max_size),
+            max_size    : max_size,
+            max_pages   : gl_limit(display, GL_MAX_ARRAY_TEXTURE_LAYERS).unwrap_or(MIN_ARRAY_TEXTURE_LAYERS),
+            pages       : Vec::new(),
+            resized     : false,
+            texture     : glium::texture::SrgbTexture2dArray::empty(display::handle(display), 2, 2, 1).unwrap(),
+        }
+    }
+
+    /// Packs all given frames into the atlas and returns their locations. If any frame does not
+    /// fit, none of the frames are stored.
+    pub fn insert_all(self: &mut Self, frames: &[RenderContextTexture]) -> Result<Vec<AtlasFrame>, Error> {
+        let checkpoint = self.checkpoint();
+        let result: Result<Vec<_>, _> = frames.iter().map(|frame| self.insert(frame)).collect();
+        if result.is_err() {
+            self.restore(checkpoint);
+        }
+        result
+    }
+
+    /// Packs given frame into the atlas and returns its location.
+    pub fn insert(self: &mut Self, frame: &RenderContextTexture) -> Result<AtlasFrame, Error> {
+
+        let width = frame.width.saturating_add(PADDING * 2);
+        let height = frame.height.saturating_add(PADDING * 2);
+
+        if width > self.max_size || height > self.max_size {
+            return Err(Error::TextureTooLarge(frame.width, frame.height));
+        }
+
+        while width > self.page_size || height > self.page_size {
+            if !self.try_grow() {
+                return Err(Error::AtlasFull);
+            }
+        }
+
+        let (page_id, x, y) = loop {
+            if let Some(position) = self.allocate(width, height) {
+                break position;
+            } else if (self.pages.len() as u32) < self.max_pages && within_budget(self.pages.len() + 1, self.page_size) {
+                self.pages.push(Page::new(self.page_size));
+                self.resized = true;
+            } else if !self.try_grow() {
+                return Err(Error::AtlasFull);
+            }
+        };
+
+        self.blit(page_id, x, y, frame);
+        self.pages[page_id].frames += 1;
+        self.pages[page_id].dirty = true;
+
+        Ok(AtlasFrame {
+            page: page_id as u32,
+            uv: Rect::new(
+                (x + PADDING) as f32,
+                (y + PADDING) as f32,
+                (x + PADDING + frame.width) as f32,
+                (y + PADDING + frame.height) as f32
+            ),
+        })
+    }
+
+    /// Releases the space of given frame. The space is reused once all frames on the same page
+    /// were released.
+    pub fn release(self: &mut Self, frame: &AtlasFrame) {
+
+        if let Some(page) = self.pages.get_mut(frame.page as usize) {
+            page.frames = page.frames.saturating_sub(1);
+            if page.frames == 0 {
+                page.shelves.clear();
+                page.height = 0;
+            }
+        }
+
+        // drop trailing empty pages to reduce memory and upload size
+
+        while self.pages.last().map_or(false, |page| page.frames == 0) {
+            self.pages.pop();
+            self.resized = true;
+        }
+    }
+
+    /// Uploads modified pages to the texture array. The array is recreated if pages were added,
+    /// removed or grown. Page size and count are validated on insert. Should the upload still
+    /// fail, the previous texture is kept and the upload is retried on the next update.
+    pub fn update(self: &mut Self, display: &Display) {
+
+        let handle = display::handle(display);
+        let page_size = self.page_size;
+
+        if self.resized {
+            let raw: Vec<_> = self.pages.iter().map(|page| glium::texture::RawImage2d {
+                data: Cow::Borrowed(&page.data[..]),
+                width: page_size,
+                height: page_size,
+                format: glium::texture::ClientFormat::U8U8U8U8,
+            }).collect();
+            let texture = if raw.is_empty() {
+                glium::texture::SrgbTexture2dArray::empty(handle, 2, 2, 1)
+            } else {
+                glium::texture::SrgbTexture2dArray::new(handle, raw)
+            };
+            if let Ok(texture) = texture {
+                self.texture = texture;
+                self.resized = false;
+                for page in self.pages.iter_mut() {
+                    page.dirty = false;
+                }
+            }
+        } else {
+            for (page_id, page) in self.pages.iter_mut().enumerate().filter(|&(_, ref page)| page.dirty) {
+                let buffer = glium::texture::pixel_buffer::PixelBuffer::new_empty(handle, page.data.len());
+                buffer.write(&page.data);
+                let layer = page_id as u32;
+                self.texture.main_level().raw_upload_from_pixel_buffer(buffer.as_slice(), 0 .. page_size, 0 .. page_size, layer .. layer + 1);
+                page.dirty = false;
+            }
+        }
+    }
+
+    /// Returns the current allocation state.
+    fn checkpoint(self: &Self) -> Checkpoint {
+        Checkpoint {
+            num_pages   : self.pages.len(),
+            pages       : self.pages.iter().map(|page| (page.shelves.clone(), page.height, page.frames)).collect(),
+        }
+    }
+
+    /// Restores a previous allocation state. Frames stored since are discarded. Grown pages are
+    /// kept at their new size.
+    fn restore(self: &mut Self, checkpoint: Checkpoint) {
+        if self.pages.len() > checkpoint.num_pages {
+            self.pages.truncate(checkpoint.num_pages);
+            self.resized = true;
+        }
+        for (page, (shelves, height, frames)) in self.pages.iter_mut().zip(checkpoint.pages) {
+            page.shelves = shelves;
+            page.height = height;
+            page.frames = frames;
+        }
+    }
+
+    /// Finds a free spot for a rectangle of given size. Returns page id and position.
+    fn allocate(self: &mut Self, width: u32, height: u32) -> Option<(usize, u32, u32)> {
+
+        let page_size = self.page_size;
+
+        for (page_id, page) in self.pages.iter_mut().enumerate() {
+
+            // use the flattest shelf that fits
+
+            let mut best: Option<usize> = None;
+
+            for (shelf_id, shelf) in page.shelves.iter().enumerate() {
+                if shelf.height >= height && page_size - shelf.width >= width {
+                    if best.map_or(true, |best_id| page.shelves[best_id].height > shelf.height) {
+                        best = Some(shelf_id);
+                    }
+                }
+            }
+
+            if let Some(shelf_id) = best {
+                let shelf = &mut page.shelves[shelf_id];
+                let x = shelf.width;
+                shelf.width += width;
+                return Some((page_id, x, shelf.y));
+            }
+
+            // otherwise open a new shelf
+
+            if page_size - page.height >= height {
+                let y = page.height;
+                page.shelves.push(Shelf { y: y, height: height, width: width });
+                page.height += height;
+                return Some((page_id, 0, y));
+            }
+        }
+
+        None
+    }
+
+    /// Doubles the page size, retaining existing page contents. Returns false if the pages can't
+    /// grow any further.
+    fn try_grow(self: &mut Self) -> bool {
+
+        let old_size = self.page_size as usize;
+        let new_size = cmp::min(self.page_size * 2, self.max_size) as usize;
+
+        if new_size == old_size || !within_budget(self.pages.len(), new_size as u32) {
+            return false;
+        }
+
+        for page in self.pages.iter_mut() {
+            let mut data = vec![(0, 0, 0, 0); new_size * new_size];
+            for row in 0..old_size {
+                data[row * new_size .. row * new_size + old_size].copy_from_slice(&page.data[row * old_size .. (row + 1) * old_size]);
+            }
+            page.data = data;
+        }
+
+        self.page_size = new_size as u32;
+        self.resized = true;
+        true
+    }
+
+    /// Copies frame to given position, extruding its edges into the padding.
+    fn blit(self: &mut Self, page_id: usize, x: u32, y: u32, frame: &RenderContextTexture) {
+
+        let page_size = self.page_size as usize;
+        let page = &mut self.pages[page_id];
+        let padding = PADDING as i32;
+
+        for dest_y in 0..(frame.height + PADDING * 2) as i32 {
+            let source_y = cmp::max(0, cmp::min(frame.height as i32 - 1, dest_y - padding)) as usize;
+            for dest_x in 0..(frame.width + PADDING * 2) as i32 {
+                let source_x = cmp::max(0, cmp::min(frame.width as i32 - 1, dest_x - padding)) as usize;
+                let source = (source_y * frame.width as usize + source_x) * 4;
+                let dest = (y as usize + dest_y as usize) * page_size + x as usize + dest_x as usize;
+                page.data[dest] = (frame.data[source], frame.data[source + 1], frame.data[source + 2], frame.data[source + 3]);
+            }
+        }
+    }
+}
+
+/// Returns whether given number of pages of given size fit into `MAX_ATLAS_BYTES`.
+fn within_budget(num_pages: usize, page_size: u32) -> bool {
+    num_pages as u64 * page_size as u64 * page_size as u64 * 4 <= MAX_ATLAS_BYTES
+}
+
+/// Queries an integer OpenGL limit. Returns None if it cannot be queried, e.g. for headless
+/// displays, which provide no access to OpenGL functions.
+fn gl_limit(display: &Display, name: u32) -> Option<u32> {
+
+    let handle = display::handle(display);
+    let address = handle.get_window()?.get_proc_address("glGetIntegerv");
+
+    if address.is_null() {
+        return None;
+    }
+
+    let mut value: i32 = 0;
+    unsafe {
+        let get_integer: extern "system" fn(u32, *mut i32) = mem::transmute(address);
+        let value = &mut value as *mut i32;
+        handle.exec_in_context(|| get_integer(name, value));
+    }
+
+    if value > 0 { Some(value as u32) } else { None }
+}
@@ -18,6 +18,12 @@ pub const ALPHA: BlendMode = BlendMode(Blend {
      constant_value: (0.0, 0.0, 0.0, 0.0)
 });
 
+pub const COPY: BlendMode = BlendMode(Blend {
+    color: BlendingFunction::AlwaysReplace,
+    alpha: BlendingFunction::AlwaysReplace,
+    constant_value: (0.0, 0.0, 0.0, 0.0),
+});
+
 pub const MAX: BlendMode = BlendMode(Blend {
     color: BlendingFunction::Max,
     alpha: BlendingFunction::Max,
@@ -1,9 +1,10 @@
 use glium;
 use glium::DisplayBuild;
-use glium::glutin::{WindowBuilder, Event, ElementState, MouseButton/*, VirtualKeyCode*/};
+use glium::glutin::{WindowBuilder, HeadlessRendererBuilder, Event, ElementState, MouseScrollDelta};
 use prelude::*;
-use core::input::{InputState, ButtonState};
-use core::monitor;
+use std::path::PathBuf;
+use core::input::{self, InputState, MouseButton};
+use core::{key, monitor, gamepad, GamepadBackend, Error};
 
 /// A struct describing a [`Display`](struct.Display.html) to be created.
 #[derive(Clone)]
@@ -31,11 +32,35 @@ impl Default for DisplayInfo {
    }
 }
 
+/// A window event, see [`Display::window_events()`](struct.Display.html#method.window_events).
+#[derive(Clone, PartialEq, Debug)]
+pub enum WindowEvent {
+    /// The window was resized to given width and height.
+    Resized(u32, u32),
+    /// The window was moved to given position.
+    Moved(i32, i32),
+    /// The window gained input focus.
+    FocusGained,
+    /// The window lost input focus.
+    FocusLost,
+    /// The window was minimized. Only reported on platforms that resize minimized windows to
+    /// zero, e.g. Windows.
+    Minimized,
+    /// The window was restored after being minimized.
+    Restored,
+    /// A file was dragged into the window and dropped.
+    DroppedFile(PathBuf),
+    /// The window was closed.
+    Closed,
+}
+
 /// A target to render to, e.g. a window or full screen.
 #[derive(Clone)]
 pub struct Display {
     handle: glium::Display,
     input_state: Arc<RwLock<InputState>>,
+    gamepad_backend: Arc<Mutex<Box<dyn GamepadBackend>>>,
+    descriptor: Arc<Mutex<DisplayInfo>>,
 }
 
 pub fn handle(display: &Display) -> &glium::Display {
@@ -50,43 +75,126 @@ impl Display {
 
     /// Creates a new instance from given [`DisplayInfo`](struct.DisplayInfo.html).
     pub fn new(descriptor: DisplayInfo) -> Display {
+        Self::try_new(descriptor).unwrap()
+    }
 
-        let mut builder = WindowBuilder::new()
-            .with_dimensions(descriptor.width, descriptor.height)
-            .with_title(descriptor.title)
-            .with_transparency(descriptor.transparent)
-            .with_decorations(descriptor.decorations);
+    /// Creates a new instance from given [`DisplayInfo`](struct.DisplayInfo.html). Returns an
+    /// error if the window or its OpenGL context could not be created.
+    pub fn try_new(descriptor: DisplayInfo) -> Result<Display, Error> {
+        Ok(Display {
+            handle: window_builder(&descriptor).build_glium().map_err(|error| Error::GlError(format!("{:?}", error)))?,
+            input_state: Arc::new(RwLock::new(InputState::new())),
+            gamepad_backend: Arc::new(Mutex::new(gamepad::default_backend())),
+            descriptor: Arc::new(Mutex::new(descriptor)),
+        })
+    }
 
-        if descriptor.monitor >= 0 {
-            let monitor = Self::monitor(descriptor.monitor as u32);
-            if monitor.is_some() {
-                builder = builder.with_fullscreen(monitor::get_id(monitor.unwrap()));
-            }
-            // !todo error
-        }
-        if descriptor.vsync {
-            builder = builder.with_vsync();
-        }
+    /// Creates a new headless instance with given dimensions.
+    ///
+    /// A headless display renders into an offscreen buffer instead of a window. It does not
+    /// generate any keyboard or mouse events, reports no gamepads unless a backend is set using
+    /// [`Display::set_gamepad_backend()`](#method.set_gamepad_backend) and window related
+    /// methods have no effect. Use
+    /// [`Renderer::read_target()`](struct.Renderer.html#method.read_target) to retrieve the
+    /// rendered pixels.
+    pub fn headless(width: u32, height: u32) -> Display {
+        Self::try_headless(width, height).unwrap()
+    }
 
-        Display {
-            handle: builder.build_glium().unwrap(),
+    /// Creates a new headless instance with given dimensions. Returns an error if no offscreen
+    /// OpenGL context could be created.
+    pub fn try_headless(width: u32, height: u32) -> Result<Display, Error> {
+        Ok(Display {
+            handle: HeadlessRendererBuilder::new(width, height).build_glium().map_err(|error| Error::GlError(format!("{:?}", error)))?,
             input_state: Arc::new(RwLock::new(InputState::new())),
-        }
+            gamepad_backend: Arc::new(Mutex::new(Box::new(gamepad::NoGamepads))),
+            descriptor: Arc::new(Mutex::new(DisplayInfo { width: width, height: height, ..DisplayInfo::default() })),
+        })
+    }
+
+    /// Returns true if the display was created using [`Display::headless()`](#method.headless).
+    pub fn is_headless(self: &Self) -> bool {
+        self.window().is_none()
     }
 
     /// Sets the window title.
     pub fn set_title(self: &Self, title: &str) {
-        self.window().set_title(title);
+        if let Some(window) = self.window() {
+            window.set_title(title);
+        }
+        self.descriptor.lock().unwrap().title = title.to_string();
+    }
+
+    /// Switches to exclusive fullscreen mode on given monitor or back to windowed mode.
+    ///
+    /// Fullscreen mode uses the monitor's current video mode. The windowing backend does not
+    /// support enumerating or switching video modes.
+    ///
+    /// For a borderless fullscreen window, use windowed mode with
+    /// [`Display::set_decorations()`](#method.set_decorations) disabled and the monitor's
+    /// dimensions passed to [`Display::set_dimensions()`](#method.set_dimensions).
+    pub fn set_fullscreen(self: &Self, monitor: Option<monitor::Monitor>) {
+        self.try_set_fullscreen(monitor).unwrap()
+    }
+
+    /// Switches to exclusive fullscreen mode on given monitor or back to windowed mode. Returns
+    /// an error if the window could not be recreated.
+    pub fn try_set_fullscreen(self: &Self, monitor: Option<monitor::Monitor>) -> Result<(), Error> {
+        self.rebuild(|descriptor| descriptor.monitor = monitor.as_ref().map_or(-1, |monitor| monitor::get_index(monitor) as i32))
+    }
+
+    /// Returns true if the display is in fullscreen mode.
+    pub fn is_fullscreen(self: &Self) -> bool {
+        self.descriptor.lock().unwrap().monitor >= 0
+    }
+
+    /// Enables or disables vertical synchronization.
+    pub fn set_vsync(self: &Self, vsync: bool) {
+        self.try_set_vsync(vsync).unwrap()
+    }
+
+    /// Enables or disables vertical synchronization. Returns an error if the window could not
+    /// be recreated.
+    pub fn try_set_vsync(self: &Self, vsync: bool) -> Result<(), Error> {
+        self.rebuild(|descriptor| descriptor.vsync = vsync)
+    }
+
+    /// Shows or hides the window title bar and borders.
+    pub fn set_decorations(self: &Self, decorations: bool) {
+        self.try_set_decorations(decorations).unwrap()
+    }
+
+    /// Shows or hides the window title bar and borders. Returns an error if the window could not
+    /// be recreated.
+    pub fn try_set_decorations(self: &Self, decorations: bool) -> Result<(), Error> {
+        self.rebuild(|descriptor| descriptor.decorations = decorations)
+    }
+
+    /// Resizes the window. In fullscreen mode, the new dimensions are applied when switching
+    /// back to windowed mode.
+    pub fn set_dimensions(self: &Self, width: u32, height: u32) {
+        let mut descriptor = self.descriptor.lock().unwrap();
+        descriptor.width = width;
+        descriptor.height = height;
+        if descriptor.monitor < 0 {
+            if let Some(window) = self.window() {
+                window.set_inner_size(width, height);
+            }
+        }
     }
 
     /// Makes the previously hidden window visible.
     pub fn show(self: &Self) {
-        self.window().show();
+        if let Some(window) = self.window() {
+            window.show();
+        }
     }
 
     /// Hides the window.
     pub fn hide(self: &Self) {
-        self.window().hide();
+        if let Some(window) = self.window() {
+            window.hide();
+        }
     }
 
     /// Enables cursor grab mode. While in this mode, the mouse cursor will be hidden and
@@ -96,22 +204,33 @@ impl Display {
     /// Grab mode will be temporarily released when the window loses focus and automatically
     /// restored once it regains focus.
     pub fn grab_cursor(self: &Self) {
-        let window = self.window();
-        window.set_cursor_state(glium::glutin::CursorState::Grab).unwrap();
-        self.input_state.write().unwrap().cursor_grabbed = true;
-        window.set_cursor_position(100, 100).unwrap();
+        if let Some(window) = self.window() {
+            window.set_cursor_state(glium::glutin::CursorState::Grab).unwrap();
+            self.input_state.write().unwrap().cursor_grabbed = true;
+            window.set_cursor_position(100, 100).unwrap();
+        }
     }
 
     /// Hides the mouse cursor while it is inside the window.
     pub fn hide_cursor(self: &Self) {
-        self.window().set_cursor_state(glium::glutin::CursorState::Hide).unwrap();
-        self.input_state.write().unwrap().cursor_grabbed = false;
+        if let Some(window) = self.window() {
+            window.set_cursor_state(glium::glutin::CursorState::Hide).unwrap();
+            self.input_state.write().unwrap().cursor_grabbed = false;
+        }
     }
 
     /// Releases a previously grabbed or hidden cursor and makes it visible again.
     pub fn free_cursor(self: &Self) {
-        self.window().set_cursor_state(glium::glutin::CursorState::Normal).unwrap();
-        self.input_state.write().unwrap().cursor_grabbed = false;
+        if let Some(window) = self.window() {
+            window.set_cursor_state(glium::glutin::CursorState::Normal).unwrap();
+            self.input_state.write().unwrap().cursor_grabbed = false;
+        }
+    }
+
+    /// Replaces the source of gamepad events, e.g. with a fake device for testing. The default
+    /// backend reads joystick devices on Linux and reports no gamepads on other platforms.
+    pub fn set_gamepad_backend<B>(self: &Self, backend: B) where B: GamepadBackend + 'static {
+        *self.gamepad_backend.lock().unwrap() = Box::new(backend);
     }
 
     /// Returns the window dimensions.
@@ -124,7 +243,7 @@ impl Display {
         let mut iter = glium::glutin::get_available_monitors();
         let result = iter.nth(index as usize);
         if result.is_some() {
-            Some(monitor::from_id(result.unwrap()))
+            Some(monitor::from_id(result.unwrap(), index))
         } else {
             None
         }
@@ -134,8 +253,8 @@ impl Display {
     pub fn monitors() -> Vec<monitor::Monitor> {
         let iter = glium::glutin::get_available_monitors();
         let mut result = Vec::<monitor::Monitor>::new();
-        for monitor in iter {
-            result.push(monitor::from_id(monitor));
+        for (index, monitor) in iter.enumerate() {
+            result.push(monitor::from_id(monitor, index as u32));
         }
         result
     }
@@ -148,57 +267,36 @@ impl Display {
     }
 */
 
-    /// Polls for events like keyboard or mouse input and changes to the window. See
-    /// [`Input`](struct.Input.html) for basic keyboard and mouse support.
+    /// Polls for events like keyboard, mouse or gamepad input and changes to the window. See
+    /// [`Input`](struct.Input.html) for basic keyboard, mouse and gamepad support.
     pub fn poll_events(self: &Self) -> &Self {
         let mut input_state = self.input_state.write().unwrap();
-        let window = self.window();
+
+        input_state.begin_frame();
+
+        let gamepad_events = self.gamepad_backend.lock().unwrap().poll();
+        gamepad::update(&mut input_state, gamepad_events);
+
+        let window = match self.window() {
+            Some(window) => window,
+            None => {
+                // headless displays don't generate events
+                input_state.dimensions = self.dimensions();
+                return self;
+            }
+        };
 
         for event in self.handle.poll_events() {
             match event {
-                // !todo vkeys seem broken
-                /*Event::KeyboardInput(element_state, scan_code, Some(virtual_code)) => {
-                    let new_state = if element_state == ElementState::Pressed { true } else { false };
-                    match virtual_code {
-                        VirtualKeyCode::LAlt => {
-                            self.alt_left = new_state;
-                        },
-                        VirtualKeyCode::RAlt => {
-                            self.alt_right = new_state;
-                        },
-                        VirtualKeyCode::LShift => {
-                            self.shift_left = new_state;
-                        },
-                        VirtualKeyCode::RShift => {
-                            self.shift_right = new_state;
-                        },
-                        VirtualKeyCode::LControl => {
-                            self.ctrl_left = new_state;
-                        },
-                        VirtualKeyCode::RControl => {
-                            self.ctrl_right = new_state;
-                        },
-                        VirtualKeyCode::Escape => {
-                            self.escape = new_state;
-                        },
-                        _ => {
-                            println!("no idea");
-                        }
+                Event::ReceivedCharacter(character) => {
+                    if let Some(text_event) = input::text_event(character) {
+                        input_state.text.push(text_event);
+                    }
+                },
+                Event::KeyboardInput(element_state, scan_code, virtual_code) => {
+                    if let Some(key) = key::from_event(scan_code, virtual_code) {
+                        input_state.key[key as usize] = input::transition(input_state.key[key as usize], element_state == ElementState::Pressed);
                     }
-                },*/
-                Event::KeyboardInput(element_state, scan_code, _) => {
-                    let new_state = if element_state == ElementState::Pressed { ButtonState::Down } else { ButtonState::Up };
-                    let current_state = input_state.key[scan_code as usize];
-
-                    input_state.key[scan_code as usize] = if current_state == ButtonState::Up && new_state == ButtonState::Down {
-                        ButtonState::Pressed
-                    } else if current_state == ButtonState::Down && new_state == ButtonState::Up {
-                        ButtonState::Released
-                    } else {
-                        new_state
-                    };
-
-                    //println!("key: {}", scan_code);
                 },
                 Event::MouseMoved(x, y) => {
                     if input_state.cursor_grabbed {
@@ -206,29 +304,34 @@ impl Display {
                         let old_mouse = input_state.mouse;
                         let delta = (x - center.0, y - center.1);
                         input_state.mouse = (old_mouse.0 + delta.0, old_mouse.1 + delta.1);
-                        input_state.mouse_delta = delta;
+                        input_state.mouse_delta = (input_state.mouse_delta.0 + delta.0, input_state.mouse_delta.1 + delta.1);
                         window.set_cursor_position(center.0, center.1).unwrap();
                     } else {
                         input_state.mouse = (x, y);
                     }
+                    let (width, height) = input_state.dimensions;
+                    input_state.mouse_inside = x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height;
+                },
+                Event::MouseWheel(delta, _) => {
+                    match delta {
+                        MouseScrollDelta::LineDelta(x, y) => {
+                            input_state.wheel_lines.0 += x;
+                            input_state.wheel_lines.1 += y;
+                        },
+                        MouseScrollDelta::PixelDelta(x, y) => {
+                            input_state.wheel_pixels.0 += x;
+                            input_state.wheel_pixels.1 += y;
+                        },
+                    }
                 },
                 Event::MouseInput(element_state, button) => {
-                    let button_id = match button {
-                        MouseButton::Left => 1,
-                        MouseButton::Middle => 2,
-                        MouseButton::Right => 3,
-                        MouseButton::Other(x) => x,
-                    };
-                    let new_state = if element_state == ElementState::Pressed { ButtonState::Down } else { ButtonState::Up };
-                    let current_state = input_state.button[button_id as usize];
-
-                    input_state.button[button_id as usize] = if current_state == ButtonState::Up && new_state == ButtonState::Down {
-                        ButtonState::Pressed
-                    } else if current_state == ButtonState::Down && new_state == ButtonState::Up {
-                        ButtonState::Released
-                    } else {
-                        new_state
-                    };
+                    let button_id = input::button_id(match button {
+                        glium::glutin::MouseButton::Left => MouseButton::Left,
+                        glium::glutin::MouseButton::Middle => MouseButton::Middle,
+                        glium::glutin::MouseButton::Right => MouseButton::Right,
+                        glium::glutin::MouseButton::Other(number) => MouseButton::Other(number),
+                    });
+                    input_state.button[button_id] = input::transition(input_state.button[button_id], element_state == ElementState::Pressed);
                 },
                 Event::Focused(true) => {
                     // restore grab after focus loss
@@ -236,9 +339,33 @@ impl Display {
                         window.set_cursor_state(glium::glutin::CursorState::Normal).unwrap();
                         window.set_cursor_state(glium::glutin::CursorState::Grab).unwrap();
                     }
+                    input_state.window_events.push(WindowEvent::FocusGained);
+                }
+                Event::Focused(false) => {
+                    // glutin reports no event when the cursor leaves the window
+                    input_state.mouse_inside = false;
+                    input_state.window_events.push(WindowEvent::FocusLost);
+                }
+                Event::Resized(width, height) => {
+                    // glutin has no minimize event, but minimized windows are resized to zero on some platforms
+                    let minimized = width == 0 || height == 0;
+                    if minimized != input_state.minimized {
+                        input_state.minimized = minimized;
+                        input_state.window_events.push(if minimized { WindowEvent::Minimized } else { WindowEvent::Restored });
+                    }
+                    if !minimized {
+                        input_state.window_events.push(WindowEvent::Resized(width, height));
+                    }
+                }
+                Event::Moved(x, y) => {
+                    input_state.window_events.push(WindowEvent::Moved(x, y));
+                }
+                Event::DroppedFile(path) => {
+                    input_state.window_events.push(WindowEvent::DroppedFile(path));
                 }
                 Event::Closed => {
                     input_state.should_close = true;
+                    input_state.window_events.push(WindowEvent::Closed);
                 }
                 _ => ()
             }
@@ -249,6 +376,12 @@ impl Display {
         self
     }
 
+    /// Returns the window events received during the last
+    /// [`Display::poll_events()`](#method.poll_events), in the order they occurred.
+    pub fn window_events(self: &Self) -> Vec<WindowEvent> {
+        self.input_state.read().unwrap().window_events.clone()
+    }
+
     /// Returns true once after the attached window was closed
     pub fn was_closed(self: &Self) -> bool {
         let mut input_state = self.input_state.write().unwrap();
@@ -257,8 +390,47 @@ impl Display {
         result
     }
 
-    /// returns a reference to the underlying glutin window
-    fn window(self: &Self) -> glium::backend::glutin_backend::WinRef {
-        self.handle.get_window().unwrap()
+    /// applies given change to the window description and recreates the window. resources
+    /// remain valid as the OpenGL context is shared with the new window
+    fn rebuild<F>(self: &Self, change: F) -> Result<(), Error> where F: FnOnce(&mut DisplayInfo) {
+        if self.is_headless() {
+            return Ok(());
+        }
+        let mut descriptor = self.descriptor.lock().unwrap();
+        change(&mut descriptor);
+        window_builder(&descriptor).rebuild_glium(&self.handle).map_err(|error| Error::GlError(format!("{:?}", error)))?;
+        // cursor state is not carried over to the new window
+        if self.input_state.read().unwrap().cursor_grabbed {
+            self.grab_cursor();
+        }
+        Ok(())
+    }
+
+    /// returns a reference to the underlying glutin window, or None for headless displays
+    fn window(self: &Self) -> Option<glium::backend::glutin_backend::WinRef> {
+        self.handle.get_window()
+    }
+}
+
+/// Creates a window builder from given description.
+fn window_builder(descriptor: &DisplayInfo) -> WindowBuilder<'static> {
+
+    let mut builder = WindowBuilder::new()
+        .with_dimensions(descriptor.width, descriptor.height)
+        .with_title(descriptor.title.clone())
+        .with_transparency(descriptor.transparent)
+        .with_decorations(descriptor.decorations);
+
+    if descriptor.monitor >= 0 {
+        let monitor = Display::monitor(descriptor.monitor as u32);
+        if monitor.is_some() {
+            builder = builder.with_fullscreen(monitor::get_id(monitor.unwrap()));
+        }
+        // !todo error
     }
+    if descriptor.vsync {
+        builder = builder.with_vsync();
+    }
+
+    builder
 }
@@ -0,0 +1,79 @@
+//! A set of predefined post processing effects for use with `PostProcessor::add()`.
+//!
+//! Each effect is a [`Program`](../struct.Program.html). Effect parameters are passed as
+//! uniforms and can be changed later using `Program::set_uniform()` with the uniform names
+//! given for each effect.
+
+use core::{RenderContext, Program, Uniform, Texture};
+use maths::Mat4;
+
+/// Blurs horizontally by given radius in pixels. Uniforms: `radius` (Float), `direction` (Vec2).
+pub fn blur_horizontal(context: &RenderContext, radius: f32) -> Program {
+    blur(context, radius, [ 1.0, 0.0 ])
+}
+
+/// Blurs vertically by given radius in pixels. Uniforms: `radius` (Float), `direction` (Vec2).
+pub fn blur_vertical(context: &RenderContext, radius: f32) -> Program {
+    blur(context, radius, [ 0.0, 1.0 ])
+}
+
+/// Makes bright areas glow. Returns three passes that need to be added in order, e.g. using
+/// `PostProcessor::extend()`.
+///
+/// Brightness above threshold (0.0 - 1.0) is blurred by radius pixels and added to the
+/// unprocessed scene, multiplied by intensity. Uniforms: `threshold` (first pass), `radius`
+/// (second and third pass) and `intensity` (third pass), all Float.
+pub fn bloom(context: &RenderContext, threshold: f32, radius: f32, intensity: f32) -> Vec<Program> {
+
+    let threshold_pass = Program::from_fragment(context, include_str!("../shader/effects/bloom_threshold.fs"));
+    threshold_pass.set_uniform("threshold", Uniform::Float(threshold));
+
+    let combine_pass = Program::from_fragment(context, include_str!("../shader/effects/bloom_combine.fs"));
+    combine_pass.set_uniform("radius", Uniform::Float(radius));
+    combine_pass.set_uniform("intensity", Uniform::Float(intensity));
+
+    vec![ threshold_pass, blur_horizontal(context, radius), combine_pass ]
+}
+
+/// Darkens the edges of the screen. Uniforms: `strength` (Float).
+pub fn vignette(context: &RenderContext, strength: f32) -> Program {
+    let program = Program::from_fragment(context, include_str!("../shader/effects/vignette.fs"));
+    program.set_uniform("strength", Uniform::Float(strength));
+    program
+}
+
+/// Darkens every other row of pixels by given intensity (0.0 - 1.0), like a CRT display.
+/// Uniforms: `intensity` (Float).
+pub fn scanlines(context: &RenderContext, intensity: f32) -> Program {
+    let program = Program::from_fragment(context, include_str!("../shader/effects/scanlines.fs"));
+    program.set_uniform("intensity", Uniform::Float(intensity));
+    program
+}
+
+/// Transforms each color (as vector r, g, b, 1.0) by given matrix, e.g. to adjust brightness,
+/// contrast or saturation. Uniforms: `color_matrix` (Mat4).
+pub fn color_matrix(context: &RenderContext, matrix: Mat4<f32>) -> Program {
+    let program = Program::from_fragment(context, include_str!("../shader/effects/color_matrix.fs"));
+    program.set_uniform("color_matrix", Uniform::Mat4(matrix));
+    program
+}
+
+/// Grades colors using a lookup table texture.
+///
+/// The texture is expected to contain size slices of size x size pixels next to each other,
+/// e.g. 256x16 pixels for size 16. Blue selects the slice, red increases to the right and
+/// green downwards within each slice. Textures bound to the program are not kept alive, so
+/// the lookup table must not be dropped while the effect is in use.
+pub fn lut(context: &RenderContext, lut: &Texture) -> Program {
+    let program = Program::from_fragment(context, include_str!("../shader/effects/lut.fs"));
+    program.set_texture("lut", lut);
+    program
+}
+
+/// Creates a blur pass in given direction.
+fn blur(context: &RenderContext, radius: f32, direction: [ f32; 2 ]) -> Program {
+    let program = Program::from_fragment(context, include_str!("../shader/effects/blur.fs"));
+    program.set_uniform("radius", Uniform::Float(radius));
+    program.set_uniform("direction", Uniform::Vec2(direction));
+    program
+}
@@ -0,0 +1,82 @@
+use prelude::*;
+use std::error;
+use image;
+
+/// An error returned by the fallible (`try_`) variants of radiant's loading functions.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read.
+    IoError(io::Error),
+    /// An image could not be decoded.
+    ImageError(image::ImageError),
+    /// A font could not be found or parsed.
+    FontError(String),
+    /// The dimensions of a sprite sheet do not match its frame layout.
+    SheetLayoutError(String),
+    /// Sprite sheet metadata could not be parsed.
+    MetadataError(String),
+    /// A texture exceeds the maximum supported size. Contains the requested width and height.
+    TextureTooLarge(u32, u32),
+    /// The sprite atlas has no space left, all of its pages are full at the maximum size.
+    AtlasFull,
+    /// An input map configuration could not be parsed.
+    InputMapError(String),
+    /// An input log could not be parsed.
+    InputLogError(String),
+    /// A tilemap or tileset file could not be parsed or is not supported.
+    TilemapError(String),
+    /// A shader program failed to compile or link.
+    ShaderError(String),
+    /// The graphics backend failed to create a context or resource.
+    GlError(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::IoError(ref error) => write!(f, "I/O error: {}", error),
+            Error::ImageError(ref error) => write!(f, "Image error: {}", error),
+            Error::FontError(ref message) => write!(f, "Font error: {}", message),
+            Error::SheetLayoutError(ref message) => write!(f, "Sprite sheet layout error: {}", message),
+            Error::MetadataError(ref message) => write!(f, "Sprite sheet metadata error: {}", message),
+            Error::TextureTooLarge(width, height) => write!(f, "Texture size {}x{} exceeds maximum", width, height),
+            Error::AtlasFull => write!(f, "Sprite atlas is full"),
+            Error::InputMapError(ref message) => write!(f, "Input map error: {}", message),
+            Error::InputLogError(ref message) => write!(f, "Input log error: {}", message),
+            Error::TilemapError(ref message) => write!(f, "Tilemap error: {}", message),
+            Error::ShaderError(ref message) => write!(f, "Shader error: {}", message),
+            Error::GlError(ref message) => write!(f, "Graphics error: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(self: &Self) -> &str {
+        match *self {
+            Error::IoError(_) => "I/O error",
+            Error::ImageError(_) => "image error",
+            Error::FontError(_) => "font error",
+            Error::SheetLayoutError(_) => "sprite sheet layout error",
+            Error::MetadataError(_) => "sprite sheet metadata error",
+            Error::TextureTooLarge(_, _) => "texture too large",
+            Error::AtlasFull => "sprite atlas full",
+            Error::InputMapError(_) => "input map error",
+            Error::InputLogError(_) => "input log error",
+            Error::TilemapError(_) => "tilemap error",
+            Error::ShaderError(_) => "shader error",
+            Error::GlError(_) => "graphics error",
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::IoError(error)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(error: image::ImageError) -> Error {
+        Error::ImageError(error)
+    }
+}
@@ -1,5 +1,5 @@
 use prelude::*;
-use core::{layer, Layer, Point, Rect, rendercontext, RenderContext};
+use core::{layer, Layer, Point, Rect, rendercontext, RenderContext, Error};
 use Color;
 use rusttype;
 use glium;
@@ -130,15 +130,27 @@ impl Font {
 
     /// Creates a font instance from a file
     pub fn from_file(context: &RenderContext, file: &str) -> Font {
-        let mut f = File::open(Path::new(file)).unwrap();
+        Self::try_from_file(context, file).unwrap()
+    }
+
+    /// Creates a font instance from a file. Returns an error if the file could not be read or
+    /// does not contain a valid font.
+    pub fn try_from_file(context: &RenderContext, file: &str) -> Result<Font, Error> {
+        let mut f = File::open(Path::new(file))?;
         let mut font_data = Vec::new();
-        f.read_to_end(&mut font_data).unwrap();
+        f.read_to_end(&mut font_data)?;
         create_font(context, font_data, 12.0)
     }
 
     /// Creates a new font instance from given FontInfo struct
     pub fn from_info(context: &RenderContext, info: FontInfo) -> Font {
-        let (font_data, _) = system_fonts::get(&build_property(&info)).unwrap();
+        Self::try_from_info(context, info).unwrap()
+    }
+
+    /// Creates a new font instance from given FontInfo struct. Returns an error if no matching
+    /// system font was found.
+    pub fn try_from_info(context: &RenderContext, info: FontInfo) -> Result<Font, Error> {
+        let (font_data, _) = system_fonts::get(&build_property(&info)).ok_or_else(|| Error::FontError(format!("no system font matching family \"{}\"", info.family)))?;
         create_font(context, font_data, info.size)
     }
 
@@ -202,14 +214,19 @@ pub fn create_cache_texture(display: &glium::Display, width: u32, height: u32) -
 }
 
 /// creates a new unique font
-fn create_font(context: &RenderContext, font_data: Vec<u8>, size: f32) -> Font {
-    Font {
+fn create_font(context: &RenderContext, font_data: Vec<u8>, size: f32) -> Result<Font, Error> {
+
+    if rusttype::FontCollection::from_bytes(&font_data[..]).into_font().is_none() {
+        return Err(Error::FontError("invalid font data".to_string()));
+    }
+
+    Ok(Font {
         data    : font_data,
         font_id : FONT_COUNTER.fetch_add(1, Ordering::Relaxed),
         size    : size,
         color   : Color::white(),
         context : context.clone(),
-    }
+    })
 }
 
 /// write text to given layer using given font
@@ -218,7 +235,7 @@ fn write(font: &Font, layer: &Layer, text: &str, x: f32, y: f32, max_width: f32,
     // !todo probably expensive, but rusttype is completely opaque. would be nice to be able to store Font::info outside of a "may or may not own" container
     let rt_font = rusttype::FontCollection::from_bytes(&font.data[..]).into_font().unwrap();
 
-    let bucket_id = 0;
+    let bucket_id = rendercontext::FONT_BUCKET;
     let glyphs = layout_paragraph(&rt_font, rusttype::Scale::uniform(font.size), max_width, &text);
     let context = rendercontext::lock(&font.context);
 
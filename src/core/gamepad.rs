@@ -0,0 +1,252 @@
+use prelude::*;
+use core::ButtonState;
+use core::input::{self, InputState};
+
+/// A gamepad event. Events are generated by a [`GamepadBackend`](trait.GamepadBackend.html)
+/// and reported by [`Input::gamepad_events()`](struct.Input.html#method.gamepad_events).
+#[derive(Clone, PartialEq, Debug)]
+pub enum GamepadEvent {
+    /// A gamepad with given id and name was connected.
+    Connected(usize, String),
+    /// The gamepad with given id was disconnected.
+    Disconnected(usize),
+    /// A button of given gamepad was pressed (true) or released (false).
+    Button(usize, u32, bool),
+    /// An axis of given gamepad was moved to given position (-1.0 to 1.0).
+    Axis(usize, u32, f32),
+}
+
+/// A source of gamepad events.
+///
+/// The default backend reads joystick devices on Linux and reports no gamepads on other
+/// platforms. A custom backend can be set using
+/// [`Display::set_gamepad_backend()`](struct.Display.html#method.set_gamepad_backend), e.g. to
+/// inject a fake device in tests.
+pub trait GamepadBackend: Send {
+    /// Returns all events that occurred since the last call.
+    fn poll(self: &mut Self) -> Vec<GamepadEvent>;
+}
+
+/// Current state of a connected gamepad.
+#[derive(Clone)]
+pub struct GamepadState {
+    pub name    : String,
+    pub buttons : Vec<ButtonState>,
+    pub axes    : Vec<f32>,
+}
+
+impl GamepadState {
+    pub fn new(name: String) -> GamepadState {
+        GamepadState {
+            name    : name,
+            buttons : Vec::new(),
+            axes    : Vec::new(),
+        }
+    }
+}
+
+/// A backend that never reports any gamepads.
+pub struct NoGamepads;
+
+impl GamepadBackend for NoGamepads {
+    fn poll(self: &mut Self) -> Vec<GamepadEvent> {
+        Vec::new()
+    }
+}
+
+/// Returns the default backend for the current platform.
+#[cfg(target_os = "linux")]
+pub fn default_backend() -> Box<dyn GamepadBackend> {
+    Box::new(linux::JoystickBackend::new())
+}
+
+/// Returns the default backend for the current platform.
+#[cfg(not(target_os = "linux"))]
+pub fn default_backend() -> Box<dyn GamepadBackend> {
+    Box::new(NoGamepads)
+}
+
+/// Updates the gamepad state from given events and stores the events for retrieval by
+/// [`Input`](struct.Input.html).
+pub fn update(input_state: &mut InputState, events: Vec<GamepadEvent>) {
+
+    for event in events.iter() {
+        match *event {
+            GamepadEvent::Connected(gamepad_id, ref name) => {
+                input_state.gamepads.insert(gamepad_id, GamepadState::new(name.clone()));
+            },
+            GamepadEvent::Disconnected(gamepad_id) => {
+                input_state.gamepads.remove(&gamepad_id);
+            },
+            GamepadEvent::Button(gamepad_id, button, pressed) => {
+                let gamepad = input_state.gamepads.entry(gamepad_id).or_insert_with(|| GamepadState::new(String::new()));
+                if gamepad.buttons.len() <= button as usize {
+                    gamepad.buttons.resize(button as usize + 1, ButtonState::Up);
+                }
+                gamepad.buttons[button as usize] = input::transition(gamepad.buttons[button as usize], pressed);
+            },
+            GamepadEvent::Axis(gamepad_id, axis, value) => {
+                let gamepad = input_state.gamepads.entry(gamepad_id).or_insert_with(|| GamepadState::new(String::new()));
+                if gamepad.axes.len() <= axis as usize {
+                    gamepad.axes.resize(axis as usize + 1, 0.0);
+                }
+                gamepad.axes[axis as usize] = value.max(-1.0).min(1.0);
+            },
+        }
+    }
+
+    input_state.gamepad_events = events;
+}
+
+/// Applies given dead-zone to an axis position, rescaling the remaining range to 0.0 - 1.0.
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone || dead_zone >= 1.0 {
+        0.0
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use prelude::*;
+    use std::collections::HashSet;
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::sync::mpsc::{channel, Sender, Receiver};
+    use std::time::{Duration, Instant};
+    use std::thread::{self, JoinHandle};
+    use super::{GamepadBackend, GamepadEvent};
+
+    // Number of /dev/input/js* devices to look for.
+    const MAX_DEVICES: usize = 16;
+
+    // Interval between checks for newly connected devices.
+    const SCAN_INTERVAL_MS: u64 = 1000;
+
+    // Interval between reads while a device has no pending events.
+    const READ_INTERVAL_MS: u64 = 5;
+
+    // O_NONBLOCK, allows reader threads to check for shutdown while a device is idle.
+    const O_NONBLOCK: i32 = 0o4000;
+
+    // js_event types, see linux/joystick.h
+    const JS_EVENT_BUTTON: u8 = 0x01;
+    const JS_EVENT_AXIS: u8 = 0x02;
+    const JS_EVENT_INIT: u8 = 0x80;
+
+    /// Reads gamepad events from the Linux joystick devices, one thread per device. The threads
+    /// are stopped and joined when the backend is dropped.
+    pub struct JoystickBackend {
+        sender      : Sender<GamepadEvent>,
+        receiver    : Receiver<GamepadEvent>,
+        open        : Arc<Mutex<HashSet<usize>>>,
+        readers     : HashMap<usize, JoinHandle<()>>,
+        shutdown    : Arc<AtomicBool>,
+        last_scan   : Option<Instant>,
+    }
+
+    impl JoystickBackend {
+        pub fn new() -> JoystickBackend {
+            let (sender, receiver) = channel();
+            JoystickBackend {
+                sender      : sender,
+                receiver    : receiver,
+                open        : Arc::new(Mutex::new(HashSet::new())),
+                readers     : HashMap::new(),
+                shutdown    : Arc::new(AtomicBool::new(false)),
+                last_scan   : None,
+            }
+        }
+
+        /// Joins the threads of disconnected devices and starts a reader thread for each newly
+        /// connected device.
+        fn scan(self: &mut Self) {
+
+            let finished: Vec<usize> = {
+                let open = self.open.lock().unwrap();
+                self.readers.keys().filter(|device_id| !open.contains(device_id)).cloned().collect()
+            };
+
+            for device_id in finished {
+                if let Some(reader) = self.readers.remove(&device_id) {
+                    let _ = reader.join();
+                }
+            }
+
+            for device_id in 0..MAX_DEVICES {
+                if self.readers.contains_key(&device_id) {
+                    continue;
+                }
+                if let Ok(file) = OpenOptions::new().read(true).custom_flags(O_NONBLOCK).open(format!("/dev/input/js{}", device_id)) {
+                    self.open.lock().unwrap().insert(device_id);
+                    let sender = self.sender.clone();
+                    let open = self.open.clone();
+                    let shutdown = self.shutdown.clone();
+                    self.readers.insert(device_id, thread::spawn(move || read_device(device_id, file, sender, open, shutdown)));
+                }
+            }
+        }
+    }
+
+    impl Drop for JoystickBackend {
+        fn drop(&mut self) {
+            self.shutdown.store(true, Ordering::Relaxed);
+            for (_, reader) in self.readers.drain() {
+                let _ = reader.join();
+            }
+        }
+    }
+
+    impl GamepadBackend for JoystickBackend {
+        fn poll(self: &mut Self) -> Vec<GamepadEvent> {
+
+            if self.last_scan.map_or(true, |last_scan| last_scan.elapsed() >= Duration::from_millis(SCAN_INTERVAL_MS)) {
+                self.last_scan = Some(Instant::now());
+                self.scan();
+            }
+
+            let mut events = Vec::new();
+            while let Ok(event) = self.receiver.try_recv() {
+                events.push(event);
+            }
+            events
+        }
+    }
+
+    /// Reads events from given device until it is disconnected or shutdown is signaled.
+    fn read_device(device_id: usize, mut file: File, sender: Sender<GamepadEvent>, open: Arc<Mutex<HashSet<usize>>>, shutdown: Arc<AtomicBool>) {
+
+        let mut name = String::new();
+        if let Ok(mut name_file) = File::open(format!("/sys/class/input/js{}/device/name", device_id)) {
+            let _ = name_file.read_to_string(&mut name);
+        }
+
+        let mut connected = sender.send(GamepadEvent::Connected(device_id, name.trim().to_string())).is_ok();
+        let mut buffer = [ 0u8; 8 ];
+
+        // struct js_event { u32 time; i16 value; u8 type; u8 number; }
+
+        while connected && !shutdown.load(Ordering::Relaxed) {
+            match file.read(&mut buffer) {
+                Ok(8) => { }
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(READ_INTERVAL_MS));
+                    continue;
+                }
+                _ => break,
+            }
+            let value = (buffer[4] as u16 | (buffer[5] as u16) << 8) as i16;
+            let event_type = buffer[6] & !JS_EVENT_INIT;
+            let number = buffer[7] as u32;
+            connected = match event_type {
+                JS_EVENT_BUTTON => sender.send(GamepadEvent::Button(device_id, number, value != 0)).is_ok(),
+                JS_EVENT_AXIS => sender.send(GamepadEvent::Axis(device_id, number, (value as f32 / 32767.0).max(-1.0))).is_ok(),
+                _ => true,
+            };
+        }
+
+        let _ = sender.send(GamepadEvent::Disconnected(device_id));
+        open.lock().unwrap().remove(&device_id);
+    }
+}
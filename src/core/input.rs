@@ -1,6 +1,9 @@
 use prelude::*;
 use std::ops::Not;
-use core::{display, Display};
+use core::{display, gamepad, Display, WindowEvent, Key, Modifiers, GamepadEvent};
+
+// Size of the mouse button state array, room for the named buttons and 256 other buttons.
+const NUM_BUTTONS: usize = 4 + 256;
 
 /// The current state of a key or mousebutton.
 #[derive(PartialEq, Copy, Clone)]
@@ -15,6 +18,13 @@ pub enum ButtonState {
     Released,
 }
 
+impl ButtonState {
+    /// Returns true if the key is held down, i.e. Pressed or Down.
+    pub fn is_down(self: &Self) -> bool {
+        *self == ButtonState::Pressed || *self == ButtonState::Down
+    }
+}
+
 impl Not for ButtonState {
     type Output = bool;
     fn not(self) -> bool {
@@ -22,11 +32,44 @@ impl Not for ButtonState {
     }
 }
 
+/// A mouse button.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    /// Any other button, identified by its platform specific number.
+    Other(u8),
+}
+
+/// A text input event, see [`Input::text_events()`](struct.Input.html#method.text_events).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TextEvent {
+    /// A printable character was entered.
+    Char(char),
+    /// The character before the cursor should be removed.
+    Backspace,
+    /// The character after the cursor should be removed.
+    Delete,
+    /// Input was confirmed using the enter/return key.
+    Enter,
+}
+
+#[derive(Clone)]
 pub struct InputState {
     pub mouse           : (i32, i32),
     pub mouse_delta     : (i32, i32),
-    pub button          : [ ButtonState; 256 ],
+    pub mouse_inside    : bool,
+    pub wheel_lines     : (f32, f32),
+    pub wheel_pixels    : (f32, f32),
+    pub button          : [ ButtonState; NUM_BUTTONS ],
     pub key             : [ ButtonState; 256 ],
+    pub text            : Vec<TextEvent>,
+    pub gamepads        : HashMap<usize, gamepad::GamepadState>,
+    pub gamepad_events  : Vec<GamepadEvent>,
+    pub dead_zone       : f32,
+    pub window_events   : Vec<WindowEvent>,
+    pub minimized       : bool,
     pub should_close    : bool,
     pub cursor_grabbed  : bool,
     pub dimensions      : (u32, u32),
@@ -37,16 +80,43 @@ impl InputState {
         InputState {
             mouse           : (0, 0),
             mouse_delta     : (0, 0),
-            button          : [ ButtonState::Up; 256 ],
+            mouse_inside    : false,
+            wheel_lines     : (0.0, 0.0),
+            wheel_pixels    : (0.0, 0.0),
+            button          : [ ButtonState::Up; NUM_BUTTONS ],
             key             : [ ButtonState::Up; 256 ],
+            text            : Vec::new(),
+            gamepads        : HashMap::new(),
+            gamepad_events  : Vec::new(),
+            dead_zone       : 0.15,
+            window_events   : Vec::new(),
+            minimized       : false,
             should_close    : false,
             cursor_grabbed  : false,
             dimensions      : (0, 0),
         }
     }
+
+    /// Prepares the state for a new frame: ages Pressed to Down and Released to Up and resets
+    /// per-frame values like mouse_delta.
+    pub fn begin_frame(self: &mut Self) {
+        for state in self.key.iter_mut().chain(self.button.iter_mut()) {
+            *state = aged(*state);
+        }
+        for gamepad in self.gamepads.values_mut() {
+            for state in gamepad.buttons.iter_mut() {
+                *state = aged(*state);
+            }
+        }
+        self.mouse_delta = (0, 0);
+        self.wheel_lines = (0.0, 0.0);
+        self.wheel_pixels = (0.0, 0.0);
+        self.text.clear();
+        self.window_events.clear();
+    }
 }
 
-/// Basic keyboard and mouse support.
+/// Basic keyboard, mouse and gamepad support.
 #[derive(Clone)]
 pub struct Input {
     input_state: Arc<RwLock<InputState>>,
@@ -91,81 +161,260 @@ impl Input {
         self.get().mouse_delta.1
     }
 
+    /// Returns true if the mouse cursor is inside the window.
+    ///
+    /// This is determined from the last reported cursor position, so it may remain true when
+    /// the cursor quickly leaves the window. It is reset when the window loses focus.
+    pub fn mouse_inside(self: &Self) -> bool {
+        self.get().mouse_inside
+    }
+
+    /// Returns the mouse wheel movement in lines since last
+    /// [`Display::poll_events()`](struct.Display.html#method.poll_events) as (horizontal, vertical).
+    /// Positive values scroll up or right. Devices that scroll by pixel are not included, see
+    /// [`Input::mouse_wheel_pixels()`](#method.mouse_wheel_pixels).
+    pub fn mouse_wheel(self: &Self) -> (f32, f32) {
+        self.get().wheel_lines
+    }
+
+    /// Returns the mouse wheel or touchpad scroll movement in pixels since last
+    /// [`Display::poll_events()`](struct.Display.html#method.poll_events) as (horizontal, vertical).
+    /// Devices that scroll by line are not included, see
+    /// [`Input::mouse_wheel()`](#method.mouse_wheel).
+    pub fn mouse_wheel_pixels(self: &Self) -> (f32, f32) {
+        self.get().wheel_pixels
+    }
+
+    /// Returns the state of the given mouse button.
+    pub fn mouse_button(self: &Self, button: MouseButton) -> ButtonState {
+        self.get().button[button_id(button)]
+    }
+
     /// Returns the state of the escape key.
     pub fn escape(self: &Self) -> ButtonState {
-        self.get().key[1]
+        self.key(Key::Escape)
     }
 
     /// Returns the state of the left alt key.
     pub fn alt_left(self: &Self) -> ButtonState {
-        self.get().key[56]
+        self.key(Key::LAlt)
     }
 
     /// Returns the state of the left ctrl key.
     pub fn ctrl_left(self: &Self) -> ButtonState {
-        self.get().key[29]
+        self.key(Key::LControl)
     }
 
     /// Returns the state of the left shift key.
     pub fn shift_left(self: &Self) -> ButtonState {
-        self.get().key[42]
+        self.key(Key::LShift)
     }
 
     /// Returns the state of the right shift key.
     pub fn shift_right(self: &Self) -> ButtonState {
-        self.get().key[54]
+        self.key(Key::RShift)
     }
 
     /// Returns the state of the cursor up key.
     pub fn cursor_up(self: &Self) -> ButtonState {
-        self.get().key[72]
+        self.key(Key::Up)
     }
 
     /// Returns the state of the cursor down key.
     pub fn cursor_down(self: &Self) -> ButtonState {
-        self.get().key[80]
+        self.key(Key::Down)
     }
 
     /// Returns the state of the cursor left key.
     pub fn cursor_left(self: &Self) -> ButtonState {
-        self.get().key[75]
+        self.key(Key::Left)
     }
 
     /// Returns the state of the cursor right key.
     pub fn cursor_right(self: &Self) -> ButtonState {
-        self.get().key[77]
+        self.key(Key::Right)
     }
 
     /// Returns the state of the enter/return key.
     pub fn enter(self: &Self) -> ButtonState {
-        self.get().key[28]
+        self.key(Key::Enter)
     }
 
     /// Returns the state of the backspace key.
     pub fn backspace(self: &Self) -> ButtonState {
-        self.get().key[14]
+        self.key(Key::Backspace)
     }
 
     /// Returns the state of the tabulator key.
     pub fn tab(self: &Self) -> ButtonState {
-        self.get().key[15]
+        self.key(Key::Tab)
     }
 
     /// Returns the state of the worthless key.
     pub fn capslock(self: &Self) -> ButtonState {
-        self.get().key[58]
+        self.key(Key::CapsLock)
     }
 
-    /// Returns the state of the given function key.
+    /// Returns the state of the given function key (1 - 15).
     pub fn f(self: &Self, index: u32) -> ButtonState {
-        if index < 1 || index > 12 {
+        let keys = [ Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12, Key::F13, Key::F14, Key::F15 ];
+        if index < 1 || index as usize > keys.len() {
             ButtonState::Up
         } else {
-            self.get().key[if index <= 10 { 58 + index as usize } else { 76 + index as usize }]
+            self.key(keys[index as usize - 1])
+        }
+    }
+
+    /// Returns the state of the given key.
+    pub fn key(self: &Self, key: Key) -> ButtonState {
+        self.get().key[key as usize]
+    }
+
+    /// Returns the state of the modifier keys.
+    pub fn modifiers(self: &Self) -> Modifiers {
+        let input_state = self.get();
+        let down = |key: Key| input_state.key[key as usize].is_down();
+        Modifiers {
+            shift   : down(Key::LShift) || down(Key::RShift),
+            ctrl    : down(Key::LControl) || down(Key::RControl),
+            alt     : down(Key::LAlt) || down(Key::RAlt),
+            logo    : down(Key::LWin) || down(Key::RWin),
+        }
+    }
+
+    /// Returns the text input received since the last
+    /// [`Display::poll_events()`](struct.Display.html#method.poll_events), in the order it was
+    /// entered. Key repeat and characters composed by input methods are included.
+    ///
+    /// Clipboard access is not supported by the windowing backend, so pasting (e.g. Ctrl+V)
+    /// produces no events. Other control characters are ignored as well.
+    pub fn text_events(self: &Self) -> Vec<TextEvent> {
+        self.get().text.clone()
+    }
+
+    /// Returns the printable characters entered since the last
+    /// [`Display::poll_events()`](struct.Display.html#method.poll_events).
+    pub fn text(self: &Self) -> String {
+        self.get().text.iter().filter_map(|event| match *event {
+            TextEvent::Char(character) => Some(character),
+            _ => None,
+        }).collect()
+    }
+
+    /// Applies the text input received since the last
+    /// [`Display::poll_events()`](struct.Display.html#method.poll_events) to given string,
+    /// appending characters and removing the last character on backspace. Returns true if
+    /// enter was pressed.
+    ///
+    /// This is intended for simple text fields that always edit at the end of the text. Pasting
+    /// is not supported, see [`Input::text_events()`](#method.text_events).
+    pub fn edit_text(self: &Self, text: &mut String) -> bool {
+        let mut confirmed = false;
+        for event in self.get().text.iter() {
+            match *event {
+                TextEvent::Char(character) => text.push(character),
+                TextEvent::Backspace => { text.pop(); },
+                TextEvent::Delete => (),
+                TextEvent::Enter => confirmed = true,
+            }
         }
+        confirmed
+    }
+
+    /// Returns the ids of all connected gamepads in ascending order.
+    pub fn gamepads(self: &Self) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.get().gamepads.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Returns the name of given gamepad or None if it is not connected.
+    pub fn gamepad_name(self: &Self, gamepad_id: usize) -> Option<String> {
+        self.get().gamepads.get(&gamepad_id).map(|gamepad| gamepad.name.clone())
+    }
+
+    /// Returns the state of given gamepad button. Buttons of disconnected gamepads are reported as Up.
+    pub fn gamepad_button(self: &Self, gamepad_id: usize, button: u32) -> ButtonState {
+        self.get().gamepads.get(&gamepad_id)
+            .and_then(|gamepad| gamepad.buttons.get(button as usize).cloned())
+            .unwrap_or(ButtonState::Up)
+    }
+
+    /// Returns the position of given gamepad axis (-1.0 to 1.0) with the dead-zone applied.
+    /// Axes of disconnected gamepads are reported as 0.0.
+    pub fn gamepad_axis(self: &Self, gamepad_id: usize, axis: u32) -> f32 {
+        let input_state = self.get();
+        input_state.gamepads.get(&gamepad_id)
+            .and_then(|gamepad| gamepad.axes.get(axis as usize).cloned())
+            .map_or(0.0, |value| gamepad::apply_dead_zone(value, input_state.dead_zone))
+    }
+
+    /// Sets the range around the center position (0.0 - 1.0) in which gamepad axes report 0.0.
+    /// Defaults to 0.15.
+    pub fn set_gamepad_dead_zone(self: &Self, dead_zone: f32) {
+        self.input_state.write().unwrap().dead_zone = dead_zone;
+    }
+
+    /// Returns the gamepad events received since the last
+    /// [`Display::poll_events()`](struct.Display.html#method.poll_events), e.g. to be notified
+    /// when gamepads are connected or disconnected.
+    pub fn gamepad_events(self: &Self) -> Vec<GamepadEvent> {
+        self.get().gamepad_events.clone()
     }
 
     fn get(self: &Self) -> RwLockReadGuard<InputState> {
         self.input_state.read().unwrap()
     }
 }
+
+/// Returns the state an input reads from.
+pub fn state<'a>(input: &'a Input) -> RwLockReadGuard<'a, InputState> {
+    input.get()
+}
+
+/// Creates an input reading from given state.
+pub fn from_state(input_state: &Arc<RwLock<InputState>>) -> Input {
+    Input {
+        input_state: input_state.clone(),
+    }
+}
+
+/// Returns the new state of a key or button after it was pressed (true) or released (false).
+pub fn transition(current_state: ButtonState, pressed: bool) -> ButtonState {
+    match (current_state.is_down(), pressed) {
+        (false, true) => ButtonState::Pressed,
+        (true, false) => ButtonState::Released,
+        _ => current_state,
+    }
+}
+
+/// Returns the state a key or button is in one frame after given state.
+fn aged(state: ButtonState) -> ButtonState {
+    match state {
+        ButtonState::Pressed => ButtonState::Down,
+        ButtonState::Released => ButtonState::Up,
+        _ => state,
+    }
+}
+
+/// Returns the index of given mouse button in the button state array. Other buttons are placed
+/// after the named ones.
+pub fn button_id(button: MouseButton) -> usize {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+        MouseButton::Other(number) => 4 + number as usize,
+    }
+}
+
+/// Converts a received character to a text event. Returns None for unsupported control characters.
+pub fn text_event(character: char) -> Option<TextEvent> {
+    match character {
+        '\u{8}' => Some(TextEvent::Backspace),
+        '\u{7f}' => Some(TextEvent::Delete),
+        '\r' | '\n' => Some(TextEvent::Enter),
+        _ if character.is_control() => None,
+        _ => Some(TextEvent::Char(character)),
+    }
+}
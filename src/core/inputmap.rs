@@ -0,0 +1,288 @@
+use prelude::*;
+use serde_json;
+use serde_json::Value;
+use core::{key, Input, ButtonState, MouseButton, Key, Error};
+
+/// A key or button an action can be bound to.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Binding {
+    /// A key on the keyboard.
+    Key(Key),
+    /// A mouse button.
+    Mouse(MouseButton),
+    /// A button on any connected gamepad.
+    GamepadButton(u32),
+}
+
+/// An input an axis can be bound to.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum AxisBinding {
+    /// A pair of keys or buttons moving the axis to -1.0 (first) and 1.0 (second).
+    Buttons(Binding, Binding),
+    /// An axis on any connected gamepad.
+    GamepadAxis(u32),
+}
+
+/// Maps named actions and axes to keys, mouse buttons and gamepad controls.
+///
+/// Each action or axis can have multiple bindings, e.g. "jump" could be bound to both the
+/// space key and a gamepad button. Bindings can be changed at runtime and saved to or loaded
+/// from a JSON configuration using [`InputMap::to_json()`](#method.to_json) and
+/// [`InputMap::from_json()`](#method.from_json).
+pub struct InputMap {
+    input   : Input,
+    actions : Mutex<HashMap<String, Vec<Binding>>>,
+    axes    : Mutex<HashMap<String, Vec<AxisBinding>>>,
+}
+
+impl InputMap {
+
+    /// Creates a new input map without any bindings.
+    pub fn new(input: &Input) -> InputMap {
+        InputMap {
+            input   : input.clone(),
+            actions : Mutex::new(HashMap::new()),
+            axes    : Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a new input map from a configuration created by [`InputMap::to_json()`](#method.to_json).
+    pub fn from_json(input: &Input, json: &str) -> InputMap {
+        Self::try_from_json(input, json).unwrap()
+    }
+
+    /// Creates a new input map from a configuration created by [`InputMap::to_json()`](#method.to_json).
+    /// Returns an error if the configuration is invalid.
+    pub fn try_from_json(input: &Input, json: &str) -> Result<InputMap, Error> {
+
+        let root: Value = serde_json::from_str(json).map_err(|error| Error::InputMapError(format!("{}", error)))?;
+        let input_map = Self::new(input);
+
+        if let Some(actions) = root.find("actions") {
+            let actions = actions.as_object().ok_or_else(|| Error::InputMapError("actions must be an object".to_string()))?;
+            for (name, bindings) in actions.iter() {
+                for binding in list(bindings, name)? {
+                    input_map.bind(name, parse_binding(binding)?);
+                }
+            }
+        }
+
+        if let Some(axes) = root.find("axes") {
+            let axes = axes.as_object().ok_or_else(|| Error::InputMapError("axes must be an object".to_string()))?;
+            for (name, bindings) in axes.iter() {
+                for binding in list(bindings, name)? {
+                    input_map.bind_axis(name, parse_axis_binding(binding)?);
+                }
+            }
+        }
+
+        Ok(input_map)
+    }
+
+    /// Returns the bindings as a JSON configuration that can be loaded using
+    /// [`InputMap::from_json()`](#method.from_json).
+    pub fn to_json(self: &Self) -> String {
+
+        let mut actions = serde_json::Map::new();
+        for (name, bindings) in self.actions.lock().unwrap().iter() {
+            actions.insert(name.clone(), Value::Array(bindings.iter().map(|binding| Value::String(binding_name(*binding))).collect()));
+        }
+
+        let mut axes = serde_json::Map::new();
+        for (name, bindings) in self.axes.lock().unwrap().iter() {
+            axes.insert(name.clone(), Value::Array(bindings.iter().map(|binding| match *binding {
+                AxisBinding::Buttons(negative, positive) => Value::Array(vec![ Value::String(binding_name(negative)), Value::String(binding_name(positive)) ]),
+                AxisBinding::GamepadAxis(axis) => Value::String(format!("gamepad_axis:{}", axis)),
+            }).collect()));
+        }
+
+        let mut root = serde_json::Map::new();
+        root.insert("actions".to_string(), Value::Object(actions));
+        root.insert("axes".to_string(), Value::Object(axes));
+
+        serde_json::to_string_pretty(&Value::Object(root)).unwrap()
+    }
+
+    /// Adds a binding to given action.
+    pub fn bind(self: &Self, action: &str, binding: Binding) -> &Self {
+        let mut actions = self.actions.lock().unwrap();
+        let bindings = actions.entry(action.to_string()).or_insert_with(Vec::new);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+
+    /// Removes a binding from given action.
+    pub fn unbind(self: &Self, action: &str, binding: Binding) -> &Self {
+        if let Some(bindings) = self.actions.lock().unwrap().get_mut(action) {
+            bindings.retain(|&existing| existing != binding);
+        }
+        self
+    }
+
+    /// Removes all bindings from given action.
+    pub fn clear(self: &Self, action: &str) -> &Self {
+        self.actions.lock().unwrap().remove(action);
+        self
+    }
+
+    /// Returns the bindings of given action.
+    pub fn bindings(self: &Self, action: &str) -> Vec<Binding> {
+        self.actions.lock().unwrap().get(action).cloned().unwrap_or_else(Vec::new)
+    }
+
+    /// Returns the names of all actions that have bindings, in alphabetical order.
+    pub fn actions(self: &Self) -> Vec<String> {
+        let mut names: Vec<String> = self.actions.lock().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Adds a binding to given axis.
+    pub fn bind_axis(self: &Self, axis: &str, binding: AxisBinding) -> &Self {
+        let mut axes = self.axes.lock().unwrap();
+        let bindings = axes.entry(axis.to_string()).or_insert_with(Vec::new);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+
+    /// Removes a binding from given axis.
+    pub fn unbind_axis(self: &Self, axis: &str, binding: AxisBinding) -> &Self {
+        if let Some(bindings) = self.axes.lock().unwrap().get_mut(axis) {
+            bindings.retain(|&existing| existing != binding);
+        }
+        self
+    }
+
+    /// Removes all bindings from given axis.
+    pub fn clear_axis(self: &Self, axis: &str) -> &Self {
+        self.axes.lock().unwrap().remove(axis);
+        self
+    }
+
+    /// Returns the bindings of given axis.
+    pub fn axis_bindings(self: &Self, axis: &str) -> Vec<AxisBinding> {
+        self.axes.lock().unwrap().get(axis).cloned().unwrap_or_else(Vec::new)
+    }
+
+    /// Returns the names of all axes that have bindings, in alphabetical order.
+    pub fn axes(self: &Self) -> Vec<String> {
+        let mut names: Vec<String> = self.axes.lock().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the state of given action, combined from all of its bindings. The action is Down
+    /// while any binding is held down and reports Pressed and Released only once when the first
+    /// binding is pressed and the last binding is released. Unknown actions are reported as Up.
+    pub fn action(self: &Self, action: &str) -> ButtonState {
+        let actions = self.actions.lock().unwrap();
+        let mut states = Vec::new();
+        for binding in actions.get(action).map_or(&[][..], |bindings| &bindings[..]) {
+            self.binding_states(*binding, &mut states);
+        }
+        combine(&states)
+    }
+
+    /// Returns the position of given axis (-1.0 to 1.0). If multiple bindings are active, the one
+    /// furthest from the center is used. Unknown axes are reported as 0.0.
+    pub fn axis(self: &Self, axis: &str) -> f32 {
+        let axes = self.axes.lock().unwrap();
+        let mut result = 0.0f32;
+        for binding in axes.get(axis).map_or(&[][..], |bindings| &bindings[..]) {
+            let value = match *binding {
+                AxisBinding::Buttons(negative, positive) => {
+                    let mut negative_states = Vec::new();
+                    let mut positive_states = Vec::new();
+                    self.binding_states(negative, &mut negative_states);
+                    self.binding_states(positive, &mut positive_states);
+                    let down = |states: &[ButtonState]| if combine(states).is_down() { 1.0 } else { 0.0 };
+                    down(&positive_states) - down(&negative_states)
+                },
+                AxisBinding::GamepadAxis(axis) => {
+                    self.input.gamepads().iter().map(|&gamepad_id| self.input.gamepad_axis(gamepad_id, axis))
+                        .fold(0.0, |a: f32, b| if b.abs() > a.abs() { b } else { a })
+                },
+            };
+            if value.abs() > result.abs() {
+                result = value;
+            }
+        }
+        result
+    }
+
+    /// Appends the current states of all inputs matching given binding.
+    fn binding_states(self: &Self, binding: Binding, states: &mut Vec<ButtonState>) {
+        match binding {
+            Binding::Key(key) => states.push(self.input.key(key)),
+            Binding::Mouse(button) => states.push(self.input.mouse_button(button)),
+            Binding::GamepadButton(button) => {
+                for gamepad_id in self.input.gamepads() {
+                    states.push(self.input.gamepad_button(gamepad_id, button));
+                }
+            },
+        }
+    }
+}
+
+/// Combines the states of multiple keys or buttons into a single state.
+fn combine(states: &[ButtonState]) -> ButtonState {
+    if states.contains(&ButtonState::Down) {
+        ButtonState::Down
+    } else if states.contains(&ButtonState::Pressed) {
+        // a binding that was released in the same frame keeps the action held down
+        if states.contains(&ButtonState::Released) { ButtonState::Down } else { ButtonState::Pressed }
+    } else if states.contains(&ButtonState::Released) {
+        ButtonState::Released
+    } else {
+        ButtonState::Up
+    }
+}
+
+/// Returns the configuration name of a binding, e.g. "key:Space".
+fn binding_name(binding: Binding) -> String {
+    match binding {
+        Binding::Key(key) => format!("key:{:?}", key),
+        Binding::Mouse(MouseButton::Other(number)) => format!("mouse:{}", number),
+        Binding::Mouse(button) => format!("mouse:{:?}", button),
+        Binding::GamepadButton(button) => format!("gamepad:{}", button),
+    }
+}
+
+/// Parses a binding from its configuration name.
+fn parse_binding(value: &Value) -> Result<Binding, Error> {
+    let name = value.as_str().ok_or_else(|| Error::InputMapError(format!("invalid binding {}", value)))?;
+    let invalid = || Error::InputMapError(format!("invalid binding {}", name));
+    let mut parts = name.splitn(2, ':');
+    let binding = match (parts.next(), parts.next()) {
+        (Some("key"), Some(key)) => Binding::Key(key::from_name(key).ok_or_else(&invalid)?),
+        (Some("mouse"), Some("Left")) => Binding::Mouse(MouseButton::Left),
+        (Some("mouse"), Some("Middle")) => Binding::Mouse(MouseButton::Middle),
+        (Some("mouse"), Some("Right")) => Binding::Mouse(MouseButton::Right),
+        (Some("mouse"), Some(number)) => Binding::Mouse(MouseButton::Other(number.parse().map_err(|_| invalid())?)),
+        (Some("gamepad"), Some(button)) => Binding::GamepadButton(button.parse().map_err(|_| invalid())?),
+        _ => return Err(invalid()),
+    };
+    Ok(binding)
+}
+
+/// Parses an axis binding from either a pair of binding names or a gamepad axis name.
+fn parse_axis_binding(value: &Value) -> Result<AxisBinding, Error> {
+    match *value {
+        Value::Array(ref pair) if pair.len() == 2 => {
+            Ok(AxisBinding::Buttons(parse_binding(&pair[0])?, parse_binding(&pair[1])?))
+        },
+        Value::String(ref name) if name.starts_with("gamepad_axis:") => {
+            name["gamepad_axis:".len()..].parse().map(AxisBinding::GamepadAxis).map_err(|_| Error::InputMapError(format!("invalid axis binding {}", name)))
+        },
+        _ => Err(Error::InputMapError(format!("invalid axis binding {}", value))),
+    }
+}
+
+/// Returns the bindings of an action or axis.
+fn list<'a>(value: &'a Value, name: &str) -> Result<&'a Vec<Value>, Error> {
+    value.as_array().ok_or_else(|| Error::InputMapError(format!("bindings of {} must be an array", name)))
+}
@@ -0,0 +1,321 @@
+use glium::glutin::VirtualKeyCode;
+
+// Defines the Key enum along with a lookup by variant name.
+macro_rules! keys {
+    ( $( #[$attr:meta] )* pub enum $enum_name:ident { $( $( #[$key_attr:meta] )* $name:ident = $code:expr, )* } ) => {
+        $( #[$attr] )*
+        pub enum $enum_name {
+            $( $( #[$key_attr] )* $name = $code, )*
+        }
+
+        /// Returns the key with given name, e.g. "Space" for `Key::Space`.
+        pub fn from_name(name: &str) -> Option<$enum_name> {
+            match name {
+                $( stringify!($name) => Some($enum_name::$name), )*
+                _ => None,
+            }
+        }
+    }
+}
+
+keys! {
+    /// A key on the keyboard.
+    ///
+    /// Keys identify physical key positions and are named after the US QWERTY layout, so
+    /// `Key::W`, `Key::A`, `Key::S` and `Key::D` refer to the same keys on any layout (e.g. Z, Q, S
+    /// and D on AZERTY keyboards). This applies to letter, digit and punctuation keys on Windows
+    /// and Linux. On other platforms, these keys are identified by the character they produce.
+    #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+    pub enum Key {
+        Escape = 1,
+        Key1 = 2,
+        Key2 = 3,
+        Key3 = 4,
+        Key4 = 5,
+        Key5 = 6,
+        Key6 = 7,
+        Key7 = 8,
+        Key8 = 9,
+        Key9 = 10,
+        Key0 = 11,
+        Minus = 12,
+        Equals = 13,
+        Backspace = 14,
+        Tab = 15,
+        Q = 16,
+        W = 17,
+        E = 18,
+        R = 19,
+        T = 20,
+        Y = 21,
+        U = 22,
+        I = 23,
+        O = 24,
+        P = 25,
+        LBracket = 26,
+        RBracket = 27,
+        Enter = 28,
+        LControl = 29,
+        A = 30,
+        S = 31,
+        D = 32,
+        F = 33,
+        G = 34,
+        H = 35,
+        J = 36,
+        K = 37,
+        L = 38,
+        Semicolon = 39,
+        Apostrophe = 40,
+        Grave = 41,
+        LShift = 42,
+        Backslash = 43,
+        Z = 44,
+        X = 45,
+        C = 46,
+        V = 47,
+        B = 48,
+        N = 49,
+        M = 50,
+        Comma = 51,
+        Period = 52,
+        Slash = 53,
+        RShift = 54,
+        NumpadMultiply = 55,
+        LAlt = 56,
+        Space = 57,
+        CapsLock = 58,
+        F1 = 59,
+        F2 = 60,
+        F3 = 61,
+        F4 = 62,
+        F5 = 63,
+        F6 = 64,
+        F7 = 65,
+        F8 = 66,
+        F9 = 67,
+        F10 = 68,
+        NumLock = 69,
+        ScrollLock = 70,
+        Numpad7 = 71,
+        Numpad8 = 72,
+        Numpad9 = 73,
+        NumpadSubtract = 74,
+        Numpad4 = 75,
+        Numpad5 = 76,
+        Numpad6 = 77,
+        NumpadAdd = 78,
+        Numpad1 = 79,
+        Numpad2 = 80,
+        Numpad3 = 81,
+        Numpad0 = 82,
+        NumpadDecimal = 83,
+        /// The additional key next to left shift on ISO keyboards.
+        NonUsBackslash = 86,
+        F11 = 87,
+        F12 = 88,
+        F13 = 100,
+        F14 = 101,
+        F15 = 102,
+        NumpadEnter = 156,
+        RControl = 157,
+        NumpadDivide = 181,
+        PrintScreen = 183,
+        RAlt = 184,
+        Pause = 197,
+        Home = 199,
+        Up = 200,
+        PageUp = 201,
+        Left = 203,
+        Right = 205,
+        End = 207,
+        Down = 208,
+        PageDown = 209,
+        Insert = 210,
+        Delete = 211,
+        LWin = 219,
+        RWin = 220,
+        Menu = 221,
+    }
+}
+
+/// The state of the modifier keys. Each modifier is considered active if either its left or right
+/// key is held down.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Modifiers {
+    pub shift   : bool,
+    pub ctrl    : bool,
+    pub alt     : bool,
+    pub logo    : bool,
+}
+
+/// Determines the key from a keyboard event.
+pub fn from_event(scan_code: u8, virtual_code: Option<VirtualKeyCode>) -> Option<Key> {
+
+    let virtual_key = virtual_code.and_then(from_virtual_code);
+
+    // keys producing characters are identified by their position, everything else by the virtual code
+
+    match virtual_key {
+        Some(key) if !is_positional(key) => Some(key),
+        _ => set1_scan_code(scan_code).and_then(from_scan_code).or(virtual_key),
+    }
+}
+
+/// Returns whether the key produces a layout dependent character.
+fn is_positional(key: Key) -> bool {
+    let code = key as u8;
+    (code >= 2 && code <= 13) || (code >= 16 && code <= 27) || (code >= 30 && code <= 41) || (code >= 43 && code <= 53) || code == 86
+}
+
+/// Returns the positional key for given set 1 scan code.
+fn from_scan_code(scan_code: u8) -> Option<Key> {
+    use self::Key::*;
+    let key = match scan_code {
+        2 => Key1, 3 => Key2, 4 => Key3, 5 => Key4, 6 => Key5, 7 => Key6, 8 => Key7, 9 => Key8, 10 => Key9, 11 => Key0,
+        12 => Minus, 13 => Equals,
+        16 => Q, 17 => W, 18 => E, 19 => R, 20 => T, 21 => Y, 22 => U, 23 => I, 24 => O, 25 => P,
+        26 => LBracket, 27 => RBracket,
+        30 => A, 31 => S, 32 => D, 33 => F, 34 => G, 35 => H, 36 => J, 37 => K, 38 => L,
+        39 => Semicolon, 40 => Apostrophe, 41 => Grave, 43 => Backslash,
+        44 => Z, 45 => X, 46 => C, 47 => V, 48 => B, 49 => N, 50 => M,
+        51 => Comma, 52 => Period, 53 => Slash, 86 => NonUsBackslash,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Converts a platform scan code to a set 1 scan code.
+#[cfg(windows)]
+fn set1_scan_code(scan_code: u8) -> Option<u8> {
+    Some(scan_code)
+}
+
+/// Converts a platform scan code to a set 1 scan code. X11 key codes are offset by 8.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn set1_scan_code(scan_code: u8) -> Option<u8> {
+    scan_code.checked_sub(8)
+}
+
+/// Converts a platform scan code to a set 1 scan code. Not supported on this platform.
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+fn set1_scan_code(_: u8) -> Option<u8> {
+    None
+}
+
+/// Returns the key for given virtual key code.
+fn from_virtual_code(virtual_code: VirtualKeyCode) -> Option<Key> {
+    use self::Key::*;
+    let key = match virtual_code {
+        VirtualKeyCode::Key1 => Key1,
+        VirtualKeyCode::Key2 => Key2,
+        VirtualKeyCode::Key3 => Key3,
+        VirtualKeyCode::Key4 => Key4,
+        VirtualKeyCode::Key5 => Key5,
+        VirtualKeyCode::Key6 => Key6,
+        VirtualKeyCode::Key7 => Key7,
+        VirtualKeyCode::Key8 => Key8,
+        VirtualKeyCode::Key9 => Key9,
+        VirtualKeyCode::Key0 => Key0,
+        VirtualKeyCode::A => A,
+        VirtualKeyCode::B => B,
+        VirtualKeyCode::C => C,
+        VirtualKeyCode::D => D,
+        VirtualKeyCode::E => E,
+        VirtualKeyCode::F => F,
+        VirtualKeyCode::G => G,
+        VirtualKeyCode::H => H,
+        VirtualKeyCode::I => I,
+        VirtualKeyCode::J => J,
+        VirtualKeyCode::K => K,
+        VirtualKeyCode::L => L,
+        VirtualKeyCode::M => M,
+        VirtualKeyCode::N => N,
+        VirtualKeyCode::O => O,
+        VirtualKeyCode::P => P,
+        VirtualKeyCode::Q => Q,
+        VirtualKeyCode::R => R,
+        VirtualKeyCode::S => S,
+        VirtualKeyCode::T => T,
+        VirtualKeyCode::U => U,
+        VirtualKeyCode::V => V,
+        VirtualKeyCode::W => W,
+        VirtualKeyCode::X => X,
+        VirtualKeyCode::Y => Y,
+        VirtualKeyCode::Z => Z,
+        VirtualKeyCode::Escape => Escape,
+        VirtualKeyCode::F1 => F1,
+        VirtualKeyCode::F2 => F2,
+        VirtualKeyCode::F3 => F3,
+        VirtualKeyCode::F4 => F4,
+        VirtualKeyCode::F5 => F5,
+        VirtualKeyCode::F6 => F6,
+        VirtualKeyCode::F7 => F7,
+        VirtualKeyCode::F8 => F8,
+        VirtualKeyCode::F9 => F9,
+        VirtualKeyCode::F10 => F10,
+        VirtualKeyCode::F11 => F11,
+        VirtualKeyCode::F12 => F12,
+        VirtualKeyCode::F13 => F13,
+        VirtualKeyCode::F14 => F14,
+        VirtualKeyCode::F15 => F15,
+        VirtualKeyCode::Snapshot | VirtualKeyCode::Sysrq => PrintScreen,
+        VirtualKeyCode::Scroll => ScrollLock,
+        VirtualKeyCode::Pause => Pause,
+        VirtualKeyCode::Insert => Insert,
+        VirtualKeyCode::Home => Home,
+        VirtualKeyCode::Delete => Delete,
+        VirtualKeyCode::End => End,
+        VirtualKeyCode::PageDown => PageDown,
+        VirtualKeyCode::PageUp => PageUp,
+        VirtualKeyCode::Left => Left,
+        VirtualKeyCode::Up => Up,
+        VirtualKeyCode::Right => Right,
+        VirtualKeyCode::Down => Down,
+        VirtualKeyCode::Back => Backspace,
+        VirtualKeyCode::Return => Enter,
+        VirtualKeyCode::Space => Space,
+        VirtualKeyCode::Numlock => NumLock,
+        VirtualKeyCode::Numpad0 => Numpad0,
+        VirtualKeyCode::Numpad1 => Numpad1,
+        VirtualKeyCode::Numpad2 => Numpad2,
+        VirtualKeyCode::Numpad3 => Numpad3,
+        VirtualKeyCode::Numpad4 => Numpad4,
+        VirtualKeyCode::Numpad5 => Numpad5,
+        VirtualKeyCode::Numpad6 => Numpad6,
+        VirtualKeyCode::Numpad7 => Numpad7,
+        VirtualKeyCode::Numpad8 => Numpad8,
+        VirtualKeyCode::Numpad9 => Numpad9,
+        VirtualKeyCode::Add => NumpadAdd,
+        VirtualKeyCode::Subtract => NumpadSubtract,
+        VirtualKeyCode::Multiply => NumpadMultiply,
+        VirtualKeyCode::Divide => NumpadDivide,
+        VirtualKeyCode::Decimal | VirtualKeyCode::NumpadComma => NumpadDecimal,
+        VirtualKeyCode::NumpadEnter => NumpadEnter,
+        VirtualKeyCode::Apostrophe => Apostrophe,
+        VirtualKeyCode::Backslash => Backslash,
+        VirtualKeyCode::Capital => CapsLock,
+        VirtualKeyCode::Comma => Comma,
+        VirtualKeyCode::Equals => Equals,
+        VirtualKeyCode::Grave => Grave,
+        VirtualKeyCode::LAlt | VirtualKeyCode::LMenu => LAlt,
+        VirtualKeyCode::RAlt | VirtualKeyCode::RMenu => RAlt,
+        VirtualKeyCode::LBracket => LBracket,
+        VirtualKeyCode::RBracket => RBracket,
+        VirtualKeyCode::LControl => LControl,
+        VirtualKeyCode::RControl => RControl,
+        VirtualKeyCode::LShift => LShift,
+        VirtualKeyCode::RShift => RShift,
+        VirtualKeyCode::LWin => LWin,
+        VirtualKeyCode::RWin => RWin,
+        VirtualKeyCode::Apps => Menu,
+        VirtualKeyCode::Minus => Minus,
+        VirtualKeyCode::OEM102 => NonUsBackslash,
+        VirtualKeyCode::Period => Period,
+        VirtualKeyCode::Semicolon => Semicolon,
+        VirtualKeyCode::Slash => Slash,
+        VirtualKeyCode::Tab => Tab,
+        _ => return None,
+    };
+    Some(key)
+}
@@ -2,7 +2,7 @@ use glium;
 use prelude::*;
 use misc::AVec;
 use maths::Mat4;
-use core::{blendmodes, BlendMode, Point, Rect, rendercontext, RenderContextData, Color, display};
+use core::{blendmodes, BlendMode, Point, Rect, rendercontext, RenderContextData, program, Program, Color, display};
 
 #[derive(Copy, Clone, Default)]
 pub struct Vertex {
@@ -16,6 +16,9 @@ pub struct Vertex {
 }
 implement_vertex!(Vertex, position, offset, rotation, color, bucket_id, texture_id, texture_uv);
 
+// Indices of the two triangles of a quad with corners top left, top right, bottom left, bottom right.
+const QUAD_INDICES: [ u32; 6 ] = [ 0, 1, 2, 1, 3, 2 ];
+
 /// A non-blocking, thread-safe drawing target.
 ///
 /// In radiant_rs, all drawing happens on layers. Layers provide transformation capabilities in
@@ -30,9 +33,13 @@ pub struct Layer {
     model_matrix    : Mutex<Mat4<f32>>,
     blend           : Mutex<BlendMode>,
     color           : Mutex<Color>,
+    program         : Mutex<Option<usize>>,
     vertex_data     : AVec<Vertex>,
     vertex_buffer   : Mutex<Option<glium::VertexBuffer<Vertex>>>,
+    index_data      : AVec<u32>,
+    index_buffer    : Mutex<Option<glium::IndexBuffer<u32>>>,
     dirty           : AtomicBool,
+    textured        : AtomicBool,
 }
 unsafe impl Send for Layer { }
 unsafe impl Sync for Layer { }
@@ -46,9 +53,13 @@ impl Layer {
             model_matrix    : Mutex::new(Mat4::<f32>::identity()),
             blend           : Mutex::new(blendmodes::ALPHA),
             color           : Mutex::new(Color::white()),
+            program         : Mutex::new(None),
             vertex_data     : AVec::new(rendercontext::INITIAL_CAPACITY * 4),
             vertex_buffer   : Mutex::new(None),
+            index_data      : AVec::new(rendercontext::INITIAL_CAPACITY * 6),
+            index_buffer    : Mutex::new(None),
             dirty           : AtomicBool::new(true),
+            textured        : AtomicBool::new(false),
         }
     }
 
@@ -112,11 +123,28 @@ impl Layer {
         self.blend.lock().unwrap()
     }
 
+    /// Sets a custom shader program to render the layer with.
+    ///
+    /// The layer does not keep the program alive. If the program is dropped, the layer is
+    /// rendered using the default program again.
+    pub fn set_program(self: &Self, program: &Program) -> &Self {
+        *self.program.lock().unwrap() = Some(program::id(program));
+        self
+    }
+
+    /// Removes the custom shader program, rendering the layer using the default program.
+    pub fn clear_program(self: &Self) -> &Self {
+        *self.program.lock().unwrap() = None;
+        self
+    }
+
     /// Removes all previously added object from the layer. Typically invoked after the layer has
     /// been rendered.
     pub fn clear(self: &Self) -> &Self {
         self.dirty.store(true, Ordering::Relaxed);
+        self.textured.store(false, Ordering::Relaxed);
         self.vertex_data.clear();
+        self.index_data.clear();
         self
     }
 
@@ -136,6 +164,10 @@ pub fn add_rect(layer: &Layer, bucket_id: u32, texture_id: u32, uv: Rect, pos: P
 
     layer.dirty.store(true, Ordering::Relaxed);
 
+    if bucket_id == rendercontext::TEXTURE_BUCKET {
+        layer.textured.store(true, Ordering::Relaxed);
+    }
+
     // corner positions relative to x/y
 
     let anchor_x = anchor.x * dim.x;
@@ -189,43 +221,185 @@ pub fn add_rect(layer: &Layer, bucket_id: u32, texture_id: u32, uv: Rect, pos: P
         texture_id  : texture_id,
         texture_uv  : [uv.1.x, uv.1.y],
     });
+
+    add_indices(layer, map.mapped_range().0, &QUAD_INDICES);
+}
+
+/// Draws an untextured quadrilateral on given layer. Corners are given relative to pos in the
+/// order top left, top right, bottom left, bottom right.
+pub fn add_quad(layer: &Layer, pos: Point, corners: [Point; 4], color: Color) {
+
+    layer.dirty.store(true, Ordering::Relaxed);
+
+    let map = layer.vertex_data.map(4);
+
+    for (index, corner) in corners.iter().enumerate() {
+        map.set(index, Vertex {
+            position    : [pos.x, pos.y],
+            offset      : [corner.x, corner.y],
+            rotation    : 0.0,
+            color       : color,
+            bucket_id   : rendercontext::COLOR_BUCKET,
+            texture_id  : 0,
+            texture_uv  : [0.0, 0.0],
+        });
+    }
+
+    add_indices(layer, map.mapped_range().0, &QUAD_INDICES);
+}
+
+/// Draws triangles on given layer. Vertices are given as offset relative to pos, texture
+/// coordinates and color. Each triple of indices into the vertices forms a triangle.
+pub fn add_triangles(layer: &Layer, bucket_id: u32, texture_id: u32, pos: Point, vertices: &[(Point, Point, Color)], indices: &[u32]) {
+
+    layer.dirty.store(true, Ordering::Relaxed);
+
+    if bucket_id == rendercontext::TEXTURE_BUCKET {
+        layer.textured.store(true, Ordering::Relaxed);
+    }
+
+    let map = layer.vertex_data.map(vertices.len());
+
+    for (index, &(offset, uv, color)) in vertices.iter().enumerate() {
+        map.set(index, Vertex {
+            position    : [pos.x, pos.y],
+            offset      : [offset.x, offset.y],
+            rotation    : 0.0,
+            color       : color,
+            bucket_id   : bucket_id,
+            texture_id  : texture_id,
+            texture_uv  : [uv.x, uv.y],
+        });
+    }
+
+    add_indices(layer, map.mapped_range().0, indices);
 }
 
-/// Uploads vertex data to the vertex buffer and returns number of vertices uploaded and the mutex-guarded vertex-buffer.
-pub fn upload<'a>(layer: &'a Layer, context: &RenderContextData) -> (MutexGuard<'a, Option<glium::VertexBuffer<Vertex>>>, usize) {
+/// Draws a quadrilateral on given layer. Corners are given as offset relative to pos, texture
+/// coordinates and color in the order top left, top right, bottom left, bottom right.
+pub fn add_corners(layer: &Layer, bucket_id: u32, texture_id: u32, pos: Point, rotation: f32, corners: [(Point, Point, Color); 4]) {
 
-    // prepare vertexbuffer if not already done
+    layer.dirty.store(true, Ordering::Relaxed);
+
+    if bucket_id == rendercontext::TEXTURE_BUCKET {
+        layer.textured.store(true, Ordering::Relaxed);
+    }
+
+    let map = layer.vertex_data.map(4);
+
+    for (index, &(offset, uv, color)) in corners.iter().enumerate() {
+        map.set(index, Vertex {
+            position    : [pos.x, pos.y],
+            offset      : [offset.x, offset.y],
+            rotation    : rotation,
+            color       : color,
+            bucket_id   : bucket_id,
+            texture_id  : texture_id,
+            texture_uv  : [uv.x, uv.y],
+        });
+    }
+
+    add_indices(layer, map.mapped_range().0, &QUAD_INDICES);
+}
+
+/// Adds indices relative to given first vertex.
+fn add_indices(layer: &Layer, first_vertex: usize, indices: &[u32]) {
+    let map = layer.index_data.map(indices.len());
+    for (position, &index) in indices.iter().enumerate() {
+        map.set(position, first_vertex as u32 + index);
+    }
+}
+
+/// Copies view matrix, blendmode, color and program from source to target layer.
+pub fn copy_properties(target: &Layer, source: &Layer) {
+    target.set_view_matrix(*source.view_matrix());
+    target.set_blendmode(*source.blendmode());
+    target.set_color(*source.color());
+    *target.program.lock().unwrap() = program_id(source);
+}
+
+/// Returns the id of the custom program assigned to the layer.
+pub fn program_id(layer: &Layer) -> Option<usize> {
+    *layer.program.lock().unwrap()
+}
+
+/// Uploads vertex and index data to the buffers and returns the mutex-guarded buffers and the number of indices uploaded.
+pub fn upload<'a>(layer: &'a Layer, context: &RenderContextData) -> (MutexGuard<'a, Option<glium::VertexBuffer<Vertex>>>, MutexGuard<'a, Option<glium::IndexBuffer<u32>>>, usize) {
 
     let mut vertex_buffer_guard = layer.vertex_buffer.lock().unwrap();
+    let mut index_buffer_guard = layer.index_buffer.lock().unwrap();
 
-    let num_vertices = {
+    let num_indices = {
         let mut vertex_buffer = vertex_buffer_guard.deref_mut();
+        let index_buffer = index_buffer_guard.deref_mut();
+        let display = display::handle(&context.display);
 
-        // prepare vertexbuffer if not already done
+        // prepare buffers if not already done
 
         if vertex_buffer.is_none() {
-            *vertex_buffer = Some(glium::VertexBuffer::empty_dynamic(display::handle(&context.display), layer.vertex_data.capacity()).unwrap());
+            *vertex_buffer = Some(glium::VertexBuffer::empty_dynamic(display, layer.vertex_data.capacity()).unwrap());
+        }
+
+        if index_buffer.is_none() {
+            *index_buffer = Some(glium::IndexBuffer::empty_dynamic(display, glium::index::PrimitiveType::TrianglesList, layer.index_data.capacity()).unwrap());
         }
 
-        // copy layer data to vertexbuffer
+        // copy layer data to buffers
 
         if layer.dirty.swap(false, Ordering::Relaxed) {
             let vertex_data = layer.vertex_data.get();
+            let index_data = layer.index_data.get();
             let num_vertices = vertex_data.len();
-            if num_vertices > 0 {
+            let num_indices = index_data.len();
+            if num_indices > 0 {
                 // resize as neccessary
                 if num_vertices > vertex_buffer.as_ref().unwrap().len() {
-                    *vertex_buffer = Some(glium::VertexBuffer::empty_dynamic(display::handle(&context.display), layer.vertex_data.capacity()).unwrap());
+                    *vertex_buffer = Some(glium::VertexBuffer::empty_dynamic(display, layer.vertex_data.capacity()).unwrap());
                 }
-                // copy data to buffer
+                if num_indices > index_buffer.as_ref().unwrap().len() {
+                    *index_buffer = Some(glium::IndexBuffer::empty_dynamic(display, glium::index::PrimitiveType::TrianglesList, layer.index_data.capacity()).unwrap());
+                }
+                // copy data to buffers
                 let vb_slice = vertex_buffer.as_ref().unwrap().slice(0 .. num_vertices).unwrap();
                 vb_slice.write(&vertex_data[0 .. num_vertices]);
+                let ib_slice = index_buffer.as_ref().unwrap().slice(0 .. num_indices).unwrap();
+                ib_slice.write(&index_data[0 .. num_indices]);
             }
-            num_vertices
+            num_indices
         } else {
-            layer.vertex_data.len()
+            layer.index_data.len()
         }
     };
 
-    (vertex_buffer_guard, num_vertices)
+    (vertex_buffer_guard, index_buffer_guard, num_indices)
+}
+
+/// Splits the first num_indices indices of the layer into consecutive batches that can be
+/// drawn with a single render-texture bound. Returns (start index, end index, texture id).
+pub fn batches(layer: &Layer, num_indices: usize) -> Vec<(usize, usize, Option<u32>)> {
+
+    if layer.textured.load(Ordering::Relaxed) == false {
+        return vec![ (0, num_indices, None) ];
+    }
+
+    let vertex_data = layer.vertex_data.get();
+    let index_data = layer.index_data.get();
+    let num_indices = cmp::min(num_indices, index_data.len());
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut current = None;
+
+    for triangle in 0..num_indices / 3 {
+        let vertex = &vertex_data[index_data[triangle * 3] as usize];
+        if vertex.bucket_id == rendercontext::TEXTURE_BUCKET {
+            if current.is_some() && current != Some(vertex.texture_id) {
+                result.push((start, triangle * 3, current));
+                start = triangle * 3;
+            }
+            current = Some(vertex.texture_id);
+        }
+    }
+
+    result.push((start, num_indices, current));
+    result
 }
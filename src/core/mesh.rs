@@ -0,0 +1,144 @@
+use prelude::*;
+use core::{layer, sprite, texture, Layer, Sprite, Texture, Point, rendercontext, Color};
+
+/// A vertex of a [`Mesh`](struct.Mesh.html).
+#[derive(Copy, Clone)]
+pub struct MeshVertex {
+    /// Position relative to the mesh origin.
+    pub x       : f32,
+    pub y       : f32,
+    /// Texture coordinates from 0.0 (left/top) to 1.0 (right/bottom) of the sprite frame or texture.
+    pub u       : f32,
+    pub v       : f32,
+    pub color   : Color,
+}
+
+/// A set of arbitrary triangles with per vertex positions, texture coordinates and colors.
+///
+/// Meshes can be drawn untextured or textured with a sprite frame or a
+/// [`Texture`](struct.Texture.html) and mix freely with sprites on the same layer. Vertices can
+/// be modified between draws, e.g. to deform sprites or to build trails and ribbons.
+///
+/// Vertices are shared between the triangles using them. Each draw appends the mesh's vertices
+/// once and three indices per triangle to the layer, which grows its buffers as needed. Sprite
+/// frames trimmed by sprite sheet metadata are mapped without their transparent border.
+#[derive(Clone)]
+pub struct Mesh {
+    vertices    : Vec<MeshVertex>,
+    triangles   : Vec<[u32; 3]>,
+}
+
+impl Mesh {
+
+    /// Creates a new, empty mesh.
+    pub fn new() -> Mesh {
+        Mesh {
+            vertices    : Vec::new(),
+            triangles   : Vec::new(),
+        }
+    }
+
+    /// Creates a grid of columns x rows cells covering width x height pixels, with texture
+    /// coordinates spanning the entire grid. Useful to deform a sprite frame.
+    pub fn grid(width: f32, height: f32, columns: u32, rows: u32) -> Mesh {
+        let mut mesh = Self::new();
+        let (columns, rows) = (cmp::max(columns, 1), cmp::max(rows, 1));
+        for row in 0..rows + 1 {
+            for column in 0..columns + 1 {
+                let (u, v) = (column as f32 / columns as f32, row as f32 / rows as f32);
+                mesh.add_vertex(u * width, v * height, u, v, Color::white());
+            }
+        }
+        for row in 0..rows {
+            for column in 0..columns {
+                let top_left = row * (columns + 1) + column;
+                let bottom_left = top_left + columns + 1;
+                mesh.add_triangle(top_left, top_left + 1, bottom_left);
+                mesh.add_triangle(top_left + 1, bottom_left + 1, bottom_left);
+            }
+        }
+        mesh
+    }
+
+    /// Adds a vertex and returns its index.
+    pub fn add_vertex(self: &mut Self, x: f32, y: f32, u: f32, v: f32, color: Color) -> u32 {
+        self.vertices.push(MeshVertex { x: x, y: y, u: u, v: v, color: color });
+        self.vertices.len() as u32 - 1
+    }
+
+    /// Adds a triangle connecting the vertices with given indices. Triangles referring to
+    /// missing vertices are skipped when drawing.
+    pub fn add_triangle(self: &mut Self, a: u32, b: u32, c: u32) -> &mut Self {
+        self.triangles.push([ a, b, c ]);
+        self
+    }
+
+    /// Returns the vertices.
+    pub fn vertices(self: &Self) -> &[MeshVertex] {
+        &self.vertices
+    }
+
+    /// Returns the vertices for modification.
+    pub fn vertices_mut(self: &mut Self) -> &mut [MeshVertex] {
+        &mut self.vertices
+    }
+
+    /// Returns the triangles as vertex indices.
+    pub fn triangles(self: &Self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+
+    /// Removes all vertices and triangles.
+    pub fn clear(self: &mut Self) -> &mut Self {
+        self.vertices.clear();
+        self.triangles.clear();
+        self
+    }
+
+    /// Draws the mesh untextured onto given layer with its origin at x/y. Vertex colors are
+    /// multiplied by given color.
+    pub fn draw(self: &Self, layer: &Layer, x: f32, y: f32, color: Color) -> &Self {
+        self.add_triangles(layer, rendercontext::COLOR_BUCKET, 0, x, y, color, |_, _| Point::new(0.0, 0.0));
+        self
+    }
+
+    /// Draws the mesh onto given layer with its origin at x/y, textured with given sprite frame.
+    /// Vertex colors are multiplied by given color.
+    pub fn draw_sprite(self: &Self, layer: &Layer, sprite: &Sprite, frame_id: u32, x: f32, y: f32, color: Color) -> &Self {
+        let frame = sprite::atlas_frame(sprite, frame_id);
+        let (uv0, uv1) = (frame.uv.0, frame.uv.1);
+        self.add_triangles(layer, rendercontext::ATLAS_BUCKET, frame.page, x, y, color, |u, v| {
+            Point::new(uv0.x + (uv1.x - uv0.x) * u, uv0.y + (uv1.y - uv0.y) * v)
+        });
+        self
+    }
+
+    /// Draws the mesh onto given layer with its origin at x/y, textured with given texture.
+    /// Vertex colors are multiplied by given color.
+    pub fn draw_texture(self: &Self, layer: &Layer, texture: &Texture, x: f32, y: f32, color: Color) -> &Self {
+        // textures are stored bottom-up, flip vertically
+        self.add_triangles(layer, rendercontext::TEXTURE_BUCKET, texture::id(texture) as u32, x, y, color, |u, v| Point::new(u, 1.0 - v));
+        self
+    }
+
+    /// Adds all triangles to the layer, mapping texture coordinates using given function.
+    fn add_triangles<F>(self: &Self, layer: &Layer, bucket_id: u32, texture_id: u32, x: f32, y: f32, color: Color, uv: F) where F: Fn(f32, f32) -> Point {
+
+        let num_vertices = self.vertices.len() as u32;
+        let indices: Vec<u32> = self.triangles.iter()
+            .filter(|triangle| triangle.iter().all(|&index| index < num_vertices))
+            .flat_map(|triangle| triangle.iter().cloned())
+            .collect();
+
+        if indices.is_empty() {
+            return;
+        }
+
+        let vertices: Vec<_> = self.vertices.iter().map(|vertex| {
+            let Color(r, g, b, a) = vertex.color;
+            (Point::new(vertex.x, vertex.y), uv(vertex.u, vertex.v), Color(r * color.0, g * color.1, b * color.2, a * color.3))
+        }).collect();
+
+        layer::add_triangles(layer, bucket_id, texture_id, Point::new(x, y), &vertices, &indices);
+    }
+}
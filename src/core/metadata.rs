@@ -0,0 +1,254 @@
+use prelude::*;
+use std::iter;
+use serde_json;
+use serde_json::Value;
+use core::Error;
+
+/// Layout of a single frame as described by sprite sheet metadata. All values are in pixels.
+pub struct FrameMetadata {
+    /// Position and size of the frame within the sheet image (before rotation).
+    pub rect        : (u32, u32, u32, u32),
+    /// Whether the frame is stored rotated clockwise by 90 degrees.
+    pub rotated     : bool,
+    /// Position of the (trimmed) frame within the untrimmed source frame.
+    pub offset      : (u32, u32),
+    /// Size of the untrimmed source frame.
+    pub source      : (u32, u32),
+    /// Pivot relative to the source frame (0.0 - 1.0).
+    pub pivot       : Option<(f32, f32)>,
+    /// Display duration in seconds.
+    pub duration    : Option<f32>,
+}
+
+/// Sprite sheet metadata as exported by TexturePacker (JSON hash/array) or Aseprite.
+pub struct SheetMetadata {
+    /// Image file name referenced by the metadata, relative to the metadata file.
+    pub image       : Option<String>,
+    pub frames      : Vec<FrameMetadata>,
+    /// Named frame ranges (first and last frame, inclusive).
+    pub ranges      : HashMap<String, (u32, u32)>,
+    /// Nine-slice border insets (left, top, right, bottom) from the first Aseprite slice with a center.
+    pub insets      : Option<(u32, u32, u32, u32)>,
+}
+
+/// Parses sprite sheet metadata from a JSON string.
+pub fn parse(json: &str) -> Result<SheetMetadata, Error> {
+
+    let root: Value = serde_json::from_str(json).map_err(|error| Error::MetadataError(format!("{}", error)))?;
+
+    // frames are given either as an array or as an object keyed by frame name
+
+    let mut named_frames: Vec<(String, &Value)> = match root.find("frames") {
+        Some(&Value::Array(ref frames)) => frames.iter().enumerate().map(|(index, frame)| {
+            (frame.find("filename").and_then(Value::as_str).map_or(index.to_string(), |name| name.to_string()), frame)
+        }).collect(),
+        Some(&Value::Object(ref frames)) => {
+            let mut frames: Vec<_> = frames.iter().map(|(name, frame)| (name.clone(), frame)).collect();
+            frames.sort_by(|a, b| natural_order(&a.0, &b.0));
+            frames
+        }
+        _ => return Err(Error::MetadataError("missing frames".to_string())),
+    };
+
+    if named_frames.is_empty() {
+        return Err(Error::MetadataError("no frames defined".to_string()));
+    }
+
+    let mut frames = Vec::with_capacity(named_frames.len());
+
+    for &(ref name, frame) in named_frames.iter() {
+        frames.push(parse_frame(name, frame)?);
+    }
+
+    let meta = root.find("meta");
+    let names: Vec<String> = named_frames.drain(..).map(|(name, _)| name).collect();
+
+    let slices = meta.and_then(|meta| meta.find("slices")).and_then(Value::as_array);
+
+    if let Some(slices) = slices {
+        apply_slice_pivots(&mut frames, slices);
+    }
+
+    Ok(SheetMetadata {
+        image   : meta.and_then(|meta| meta.find("image")).and_then(Value::as_str).map(|image| image.to_string()),
+        ranges  : parse_ranges(&root, &names)?,
+        insets  : match slices { Some(slices) => slice_insets(&frames, slices)?, None => None },
+        frames  : frames,
+    })
+}
+
+/// Parses a single frame entry.
+fn parse_frame(name: &str, frame: &Value) -> Result<FrameMetadata, Error> {
+
+    let (x, y, width, height) = rect(frame.find("frame")).ok_or_else(|| Error::MetadataError(format!("invalid frame rect for frame {}", name)))?;
+    let (offset_x, offset_y, _, _) = rect(frame.find("spriteSourceSize")).unwrap_or((0, 0, width, height));
+    let source = match size(frame.find("sourceSize")) {
+        Some(source) => source,
+        None => match (offset_x.checked_add(width), offset_y.checked_add(height)) {
+            (Some(source_width), Some(source_height)) => (source_width, source_height),
+            _ => return Err(Error::MetadataError(format!("invalid source size for frame {}", name))),
+        },
+    };
+
+    Ok(FrameMetadata {
+        rect        : (x, y, width, height),
+        rotated     : frame.find("rotated").and_then(Value::as_bool).unwrap_or(false),
+        offset      : (offset_x, offset_y),
+        source      : source,
+        pivot       : frame.find("pivot").and_then(|pivot| Some((number(pivot, "x")? as f32, number(pivot, "y")? as f32))),
+        duration    : frame.find("duration").and_then(Value::as_f64).map(|duration| duration as f32 / 1000.0),
+    })
+}
+
+/// Parses Aseprite frame tags and TexturePacker animations into named frame ranges.
+fn parse_ranges(root: &Value, names: &[String]) -> Result<HashMap<String, (u32, u32)>, Error> {
+
+    let mut ranges = HashMap::new();
+    let num_frames = names.len() as u32;
+
+    if let Some(tags) = root.find_path(&[ "meta", "frameTags" ]).and_then(Value::as_array) {
+        for tag in tags {
+            let name = tag.find("name").and_then(Value::as_str);
+            let from = tag.find("from").and_then(Value::as_u64);
+            let to = tag.find("to").and_then(Value::as_u64);
+            match (name, from, to) {
+                (Some(name), Some(from), Some(to)) if from < num_frames as u64 && to < num_frames as u64 => {
+                    // reversed ranges are played backwards
+                    let range = if tag.find("direction").and_then(Value::as_str) == Some("reverse") { (to as u32, from as u32) } else { (from as u32, to as u32) };
+                    ranges.insert(name.to_string(), range);
+                }
+                _ => return Err(Error::MetadataError("invalid frame tag".to_string())),
+            }
+        }
+    }
+
+    if let Some(animations) = root.find("animations").and_then(Value::as_object) {
+        for (name, animation) in animations {
+            let frame_ids = animation.as_array().and_then(|frames| {
+                frames.iter().map(|frame| frame.as_str().and_then(|frame| names.iter().position(|name| name == frame))).collect::<Option<Vec<_>>>()
+            });
+            match frame_ids {
+                Some(ref frame_ids) if frame_ids.len() > 0 && frame_ids.windows(2).all(|pair| pair[1] == pair[0] + 1) => {
+                    ranges.insert(name.clone(), (frame_ids[0] as u32, frame_ids[frame_ids.len() - 1] as u32));
+                }
+                _ => return Err(Error::MetadataError(format!("animation {} does not reference a consecutive range of frames", name))),
+            }
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// Applies pivots defined by the first Aseprite slice that has any to frames without a pivot.
+fn apply_slice_pivots(frames: &mut [FrameMetadata], slices: &[Value]) {
+
+    for slice in slices {
+
+        let mut keys: Vec<(u32, (f32, f32))> = slice.find("keys").and_then(Value::as_array).map_or(Vec::new(), |keys| {
+            keys.iter().filter_map(|key| {
+                let frame_id = key.find("frame").and_then(Value::as_u64)? as u32;
+                let (x, y, _, _) = rect(key.find("bounds"))?;
+                let pivot = key.find("pivot")?;
+                Some((frame_id, (x as f32 + number(pivot, "x")? as f32, y as f32 + number(pivot, "y")? as f32)))
+            }).collect()
+        });
+
+        if keys.is_empty() {
+            continue;
+        }
+
+        keys.sort_by_key(|key| key.0);
+
+        // each key applies to its frame and all following frames up to the next key
+
+        for (frame_id, frame) in frames.iter_mut().enumerate() {
+            if let Some(&(_, (x, y))) = keys.iter().rev().find(|key| key.0 as usize <= frame_id) {
+                if frame.pivot.is_none() && frame.source.0 > 0 && frame.source.1 > 0 {
+                    frame.pivot = Some((x / frame.source.0 as f32, y / frame.source.1 as f32));
+                }
+            }
+        }
+
+        break;
+    }
+}
+
+/// Returns the border insets defined by the center of the first Aseprite slice that has one.
+fn slice_insets(frames: &[FrameMetadata], slices: &[Value]) -> Result<Option<(u32, u32, u32, u32)>, Error> {
+    let insets = slices.iter().filter_map(|slice| {
+        let key = slice.find("keys").and_then(Value::as_array)?.iter().find(|key| key.find("center").is_some())?;
+        let frame = frames.get(key.find("frame").and_then(Value::as_u64).unwrap_or(0) as usize)?;
+        let (x, y, _, _) = rect(key.find("bounds"))?;
+        Some(center_insets(frame.source, (x, y), rect(key.find("center"))?))
+    }).next();
+    match insets {
+        Some(insets) => insets.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Computes border insets from a slice center relative to given slice position.
+fn center_insets(source: (u32, u32), position: (u32, u32), center: (u32, u32, u32, u32)) -> Result<(u32, u32, u32, u32), Error> {
+    let (center_x, center_y, center_width, center_height) = center;
+    let left = position.0.checked_add(center_x);
+    let top = position.1.checked_add(center_y);
+    let right = left.and_then(|left| left.checked_add(center_width));
+    let bottom = top.and_then(|top| top.checked_add(center_height));
+    match (left, top, right, bottom) {
+        (Some(left), Some(top), Some(right), Some(bottom)) => Ok((left, top, source.0.saturating_sub(right), source.1.saturating_sub(bottom))),
+        _ => Err(Error::MetadataError("invalid slice center".to_string())),
+    }
+}
+
+/// Reads an object containing x, y, w and h.
+fn rect(value: Option<&Value>) -> Option<(u32, u32, u32, u32)> {
+    let value = value?;
+    Some((number(value, "x")? as u32, number(value, "y")? as u32, number(value, "w")? as u32, number(value, "h")? as u32))
+}
+
+/// Reads an object containing w and h.
+fn size(value: Option<&Value>) -> Option<(u32, u32)> {
+    let value = value?;
+    Some((number(value, "w")? as u32, number(value, "h")? as u32))
+}
+
+/// Reads a numeric field of an object.
+fn number(value: &Value, key: &str) -> Option<f64> {
+    value.find(key).and_then(Value::as_f64)
+}
+
+/// Compares frame names so that embedded numbers are ordered numerically (frame 2 before frame 10).
+fn natural_order(a: &str, b: &str) -> cmp::Ordering {
+
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().cloned(), b.peek().cloned()) {
+            (Some(char_a), Some(char_b)) if char_a.is_digit(10) && char_b.is_digit(10) => {
+                let ordering = take_number(&mut a).cmp(&take_number(&mut b));
+                if ordering != cmp::Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(char_a), Some(char_b)) => {
+                if char_a != char_b {
+                    return char_a.cmp(&char_b);
+                }
+                a.next();
+                b.next();
+            }
+            (char_a, char_b) => return char_a.cmp(&char_b),
+        }
+    }
+}
+
+/// Consumes consecutive digits and returns their value.
+fn take_number<I>(chars: &mut iter::Peekable<I>) -> u64 where I: Iterator<Item=char> {
+    let mut number = 0u64;
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+        number = number.saturating_mul(10).saturating_add(digit as u64);
+        chars.next();
+    }
+    number
+}
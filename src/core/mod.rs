@@ -2,6 +2,8 @@ mod blendmode;
 mod display;
 mod input;
 mod layer;
+mod shapes;
+mod mesh;
 mod renderer;
 mod sprite;
 mod font;
@@ -9,18 +11,45 @@ mod rendercontext;
 mod scene;
 mod color;
 mod monitor;
+mod texture;
+mod atlas;
+mod error;
+mod metadata;
+mod animation;
+mod program;
+mod key;
+mod gamepad;
+mod inputmap;
+mod recording;
+mod postprocessor;
+mod scaling;
+mod tiled;
+mod tilemap;
+pub mod effects;
 
 pub use self::blendmode::{blendmodes, BlendMode};
-pub use self::input::{Input, ButtonState};
-pub use self::display::{Display, DisplayInfo};
-pub use self::sprite::Sprite;
+pub use self::input::{Input, ButtonState, MouseButton, TextEvent};
+pub use self::key::{Key, Modifiers};
+pub use self::gamepad::{GamepadEvent, GamepadBackend};
+pub use self::inputmap::{InputMap, Binding, AxisBinding};
+pub use self::recording::{InputLog, InputRecorder, InputReplay};
+pub use self::display::{Display, DisplayInfo, WindowEvent};
+pub use self::sprite::{Sprite, DrawParams};
 pub use self::renderer::Renderer;
 pub use self::font::{Font, FontInfo, FontCache};
 pub use self::layer::Layer;
-pub use self::rendercontext::{RenderContext, RenderContextData, RenderContextTexture, RenderContextTextureArray};
+pub use self::mesh::{Mesh, MeshVertex};
+pub use self::tilemap::{Tilemap, Tile};
+pub use self::rendercontext::{RenderContext, RenderContextData, RenderContextTexture};
 pub use self::color::Color;
 pub use self::scene::*;
 pub use self::monitor::Monitor;
+pub use self::texture::Texture;
+pub use self::error::Error;
+pub use self::program::{Program, Uniform};
+pub use self::postprocessor::PostProcessor;
+pub use self::scaling::ScalingPolicy;
+pub use self::animation::{Animation, AnimationPlayer, PlaybackMode};
 
 use prelude::*;
 
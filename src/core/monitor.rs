@@ -3,6 +3,7 @@ use glium;
 /// An individual monitor, returned from [`Display::monitors()`](struct.Display.html#method.monitors).
 pub struct Monitor {
     id: glium::glutin::MonitorId,
+    index: u32,
 }
 
 impl Monitor {
@@ -33,8 +34,13 @@ pub fn get_id(monitor: Monitor) -> glium::glutin::MonitorId {
     monitor.id
 }
 
-pub fn from_id(id: glium::glutin::MonitorId) -> Monitor {
+pub fn get_index(monitor: &Monitor) -> u32 {
+    monitor.index
+}
+
+pub fn from_id(id: glium::glutin::MonitorId, index: u32) -> Monitor {
     Monitor {
-        id: id
+        id: id,
+        index: index,
     }
 }
@@ -0,0 +1,149 @@
+use prelude::*;
+use core::{blendmodes, renderer, BlendMode, Layer, Renderer, RenderContext, Program, Texture, Color};
+use maths::Mat4;
+
+/// Intermediate textures used by a post processor.
+struct Targets {
+    scene   : Texture,
+    buffers : [ Texture; 2 ],
+}
+
+impl Targets {
+    fn new(context: &RenderContext, dimensions: (u32, u32)) -> Targets {
+        let (width, height) = dimensions;
+        Targets {
+            scene   : Texture::new(context, width, height),
+            buffers : [ Texture::new(context, width, height), Texture::new(context, width, height) ],
+        }
+    }
+}
+
+/// A chain of full-screen effects applied to the output of one or more layers.
+///
+/// Use [`Renderer::postprocess()`](struct.Renderer.html#method.postprocess) to draw layers
+/// into an intermediate texture, which is then passed through each of the post processor's
+/// effects in turn before the result is drawn onto the current target.
+///
+/// Effects are [`Program`](struct.Program.html)s that sample the output of the previous effect
+/// from the `render_texture` uniform at `v_tex_coords`. The unprocessed layer output is also
+/// available from the `scene` uniform. See [effects](effects/index.html) for a list of
+/// predefined effects.
+pub struct PostProcessor {
+    passes      : Mutex<Vec<Program>>,
+    targets     : Mutex<Option<Arc<Targets>>>,
+    blendmode   : Mutex<BlendMode>,
+    layer       : Layer,
+    context     : RenderContext,
+}
+
+impl PostProcessor {
+
+    /// Creates a new post processor without any effects.
+    pub fn new(context: &RenderContext) -> PostProcessor {
+        PostProcessor {
+            passes      : Mutex::new(Vec::new()),
+            targets     : Mutex::new(None),
+            blendmode   : Mutex::new(blendmodes::ALPHA),
+            layer       : Layer::new(1, 1),
+            context     : context.clone(),
+        }
+    }
+
+    /// Appends an effect to the chain.
+    pub fn add(self: &Self, program: Program) -> &Self {
+        self.passes.lock().unwrap().push(program);
+        self
+    }
+
+    /// Appends multiple effects to the chain, e.g. the passes returned by
+    /// [`effects::bloom()`](effects/fn.bloom.html).
+    pub fn extend<I>(self: &Self, programs: I) -> &Self where I: IntoIterator<Item=Program> {
+        self.passes.lock().unwrap().extend(programs);
+        self
+    }
+
+    /// Removes all effects from the chain.
+    pub fn clear(self: &Self) -> &Self {
+        self.passes.lock().unwrap().clear();
+        self
+    }
+
+    /// Returns the number of effects in the chain.
+    pub fn len(self: &Self) -> usize {
+        self.passes.lock().unwrap().len()
+    }
+
+    /// Sets the blendmode used to draw the final result onto the target. Defaults to
+    /// [`blendmodes::ALPHA`](blendmodes/constant.ALPHA.html).
+    pub fn set_blendmode(self: &Self, blendmode: BlendMode) -> &Self {
+        self.blendmode.lock().unwrap().set(blendmode);
+        self
+    }
+}
+
+/// Renders the layers drawn by given function into an intermediate texture, applies all
+/// effects and draws the result onto the renderer's current target.
+pub fn process<F>(postprocessor: &PostProcessor, renderer: &Renderer, dimensions: (u32, u32), draw_func: F) where F: FnMut() {
+
+    let (width, height) = dimensions;
+
+    // (re)create intermediate textures when the target size changes. the lock is released before
+    // drawing, so draw_func may use the post processor as well
+
+    let targets = {
+        let mut targets_guard = postprocessor.targets.lock().unwrap();
+        if targets_guard.as_ref().map_or(true, |targets| targets.scene.dimensions() != dimensions) {
+            *targets_guard = Some(Arc::new(Targets::new(&postprocessor.context, dimensions)));
+        }
+        targets_guard.as_ref().unwrap().clone()
+    };
+
+    // draw layers into the scene texture
+
+    // the scene texture has the target's dimensions, so scaling is applied here instead of to the
+    // final result
+
+    targets.scene.clear(Color::transparent());
+    let previous_override = renderer::override_scaling(renderer, Some(true));
+    renderer.render_to(&targets.scene, draw_func);
+    renderer::override_scaling(renderer, Some(false));
+
+    // apply each effect, ping-ponging between the two buffers. the last effect draws onto the actual target
+
+    let passes = postprocessor.passes.lock().unwrap();
+    let layer = &postprocessor.layer;
+    let mut source = &targets.scene;
+
+    layer.set_view_matrix(Mat4::viewport(width as f32, height as f32));
+
+    for (pass_id, pass) in passes.iter().enumerate() {
+
+        pass.set_texture("scene", &targets.scene);
+        layer.clear();
+        layer.set_program(pass);
+        source.draw(layer, width as f32 / 2.0, height as f32 / 2.0, Color::white());
+
+        if pass_id + 1 < passes.len() {
+            let target = &targets.buffers[pass_id % 2];
+            target.clear(Color::transparent());
+            layer.set_blendmode(blendmodes::COPY);
+            renderer.render_to(target, || { renderer.draw_layer(layer); });
+            source = target;
+        } else {
+            layer.set_blendmode(*postprocessor.blendmode.lock().unwrap());
+            renderer.draw_layer(layer);
+        }
+    }
+
+    // without effects, just copy the scene to the target
+
+    if passes.len() == 0 {
+        layer.clear();
+        layer.clear_program();
+        layer.set_blendmode(*postprocessor.blendmode.lock().unwrap());
+        source.draw(layer, width as f32 / 2.0, height as f32 / 2.0, Color::white());
+        renderer.draw_layer(layer);
+    }
+
+    renderer::override_scaling(renderer, previous_override);
+}
@@ -0,0 +1,164 @@
+use prelude::*;
+use glium;
+use glium::uniforms::{Uniforms, UniformValue, AsUniformValue};
+use core::{display, rendercontext, RenderContext, texture, Texture, Color, Error};
+use maths::Mat4;
+
+static PROGRAM_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// A value passed to a custom shader [`Program`](struct.Program.html).
+#[derive(Copy, Clone)]
+pub enum Uniform {
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Mat4(Mat4<f32>),
+    Color(Color),
+}
+
+#[doc(hidden)]
+impl AsUniformValue for Uniform {
+    fn as_uniform_value(&self) -> UniformValue {
+        match *self {
+            Uniform::Bool(value) => UniformValue::Bool(value),
+            Uniform::Int(value) => UniformValue::SignedInt(value),
+            Uniform::UInt(value) => UniformValue::UnsignedInt(value),
+            Uniform::Float(value) => UniformValue::Float(value),
+            Uniform::Vec2(value) => UniformValue::Vec2(value),
+            Uniform::Vec3(value) => UniformValue::Vec3(value),
+            Uniform::Vec4(value) => UniformValue::Vec4(value),
+            Uniform::Mat4(ref value) => value.as_uniform_value(),
+            Uniform::Color(ref value) => value.as_uniform_value(),
+        }
+    }
+}
+
+/// Internal data of a program
+pub struct ProgramData {
+    pub program     : glium::Program,
+    pub uniforms    : HashMap<String, Uniform>,
+    pub textures    : HashMap<String, usize>,
+}
+
+/// A custom shader program used to render a [`Layer`](struct.Layer.html).
+///
+/// Programs are assigned to layers using [`Layer::set_program()`](struct.Layer.html#method.set_program).
+/// They use the same vertex attributes as the default program (`position`, `offset`, `rotation`,
+/// `color`, `bucket_id`, `texture_id` and `texture_uv`) and receive the same uniforms
+/// (`view_matrix`, `model_matrix`, `global_color`, `font_cache`, `atlas` and `render_texture`)
+/// in addition to those set via [`Program::set_uniform()`](#method.set_uniform).
+///
+/// The easiest way to write a custom program is to copy the default fragment shader from
+/// `src/shader/default.fs` and pass the modified version to
+/// [`Program::from_fragment()`](#method.from_fragment). Its inputs from the default vertex shader
+/// are `v_tex_coords`, `v_color`, `v_texture_id` and `v_bucket_id`.
+pub struct Program {
+    program_id  : usize,
+    context     : RenderContext,
+}
+
+impl Program {
+
+    /// Creates a new program from given vertex and fragment shader sources.
+    pub fn new(context: &RenderContext, vertex_shader: &str, fragment_shader: &str) -> Program {
+        Self::try_new(context, vertex_shader, fragment_shader).unwrap()
+    }
+
+    /// Creates a new program from given vertex and fragment shader sources. Returns an error if
+    /// the program fails to compile or link.
+    pub fn try_new(context: &RenderContext, vertex_shader: &str, fragment_shader: &str) -> Result<Program, Error> {
+
+        let program_id = PROGRAM_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mut context_data = rendercontext::lock(context);
+
+        let program = glium::Program::from_source(display::handle(&context_data.display), vertex_shader, fragment_shader, None)
+            .map_err(|error| Error::ShaderError(format!("{:?}", error)))?;
+
+        context_data.programs.insert(program_id, ProgramData {
+            program     : program,
+            uniforms    : HashMap::new(),
+            textures    : HashMap::new(),
+        });
+
+        Ok(Program {
+            program_id  : program_id,
+            context     : context.clone(),
+        })
+    }
+
+    /// Creates a new program from given fragment shader source, using the default vertex shader.
+    pub fn from_fragment(context: &RenderContext, fragment_shader: &str) -> Program {
+        Self::try_from_fragment(context, fragment_shader).unwrap()
+    }
+
+    /// Creates a new program from given fragment shader source, using the default vertex shader.
+    /// Returns an error if the program fails to compile or link.
+    pub fn try_from_fragment(context: &RenderContext, fragment_shader: &str) -> Result<Program, Error> {
+        Self::try_new(context, include_str!("../shader/default.vs"), fragment_shader)
+    }
+
+    /// Sets the value of a uniform. Uniforms not used by the program are ignored.
+    pub fn set_uniform(self: &Self, name: &str, value: Uniform) -> &Self {
+        if let Some(data) = rendercontext::lock(&self.context).programs.get_mut(&self.program_id) {
+            data.uniforms.insert(name.to_string(), value);
+        }
+        self
+    }
+
+    /// Binds a texture to a sampler2D uniform, e.g. a palette for palette swaps. The binding is
+    /// removed when the texture is dropped.
+    pub fn set_texture(self: &Self, name: &str, texture: &Texture) -> &Self {
+        if let Some(data) = rendercontext::lock(&self.context).programs.get_mut(&self.program_id) {
+            data.textures.insert(name.to_string(), texture::id(texture));
+        }
+        self
+    }
+}
+
+impl Drop for Program {
+    fn drop(self: &mut Self) {
+        rendercontext::lock(&self.context).programs.remove(&self.program_id);
+    }
+}
+
+/// Returns the id the program is registered under in the render context.
+pub fn id(program: &Program) -> usize {
+    program.program_id
+}
+
+/// Combines the default uniforms with the uniforms of a custom program.
+pub struct ProgramUniforms<'a, U: Uniforms> {
+    defaults    : U,
+    program     : Option<&'a ProgramData>,
+    textures    : &'a HashMap<usize, glium::texture::Texture2d>,
+}
+
+impl<'a, U: Uniforms> ProgramUniforms<'a, U> {
+    pub fn new(defaults: U, program: Option<&'a ProgramData>, textures: &'a HashMap<usize, glium::texture::Texture2d>) -> Self {
+        ProgramUniforms {
+            defaults    : defaults,
+            program     : program,
+            textures    : textures,
+        }
+    }
+}
+
+impl<'a, U: Uniforms> Uniforms for ProgramUniforms<'a, U> {
+    fn visit_values<'b, F: FnMut(&str, UniformValue<'b>)>(&'b self, mut output: F) {
+        self.defaults.visit_values(&mut output);
+        if let Some(program) = self.program {
+            for (name, value) in program.uniforms.iter() {
+                output(name, value.as_uniform_value());
+            }
+            for (name, texture_id) in program.textures.iter() {
+                if let Some(texture) = self.textures.get(texture_id) {
+                    output(name, UniformValue::Texture2d(texture, None));
+                }
+            }
+        }
+    }
+}
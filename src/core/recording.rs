@@ -0,0 +1,337 @@
+use prelude::*;
+use std::time::Duration;
+use serde_json;
+use serde_json::Value;
+use core::input::{self, InputState, ButtonState, TextEvent};
+use core::gamepad::GamepadState;
+use core::{Input, GamepadEvent, Error};
+use misc::LoopState;
+
+/// The input state and loop state of a single recorded frame.
+#[derive(Clone)]
+struct InputFrame {
+    loop_state  : LoopState,
+    input_state : InputState,
+}
+
+/// A log of per-frame input states, created by an [`InputRecorder`](struct.InputRecorder.html)
+/// and played back by an [`InputReplay`](struct.InputReplay.html).
+///
+/// Only the state reported by [`Input`](struct.Input.html) is recorded: keys, mouse, text,
+/// gamepads and the window dimensions. Window events, the minimized and closed state and cursor
+/// grabbing are reported by the [`Display`](struct.Display.html) and are not replayed, so code
+/// depending on them, e.g. on [`Display::was_closed()`](struct.Display.html#method.was_closed),
+/// has to be driven separately during a replay.
+#[derive(Clone)]
+pub struct InputLog {
+    frames: Vec<InputFrame>,
+}
+
+impl InputLog {
+
+    /// Creates a new, empty log.
+    pub fn new() -> InputLog {
+        InputLog {
+            frames: Vec::new(),
+        }
+    }
+
+    /// Loads a log from given file.
+    pub fn from_file(file: &str) -> InputLog {
+        Self::try_from_file(file).unwrap()
+    }
+
+    /// Loads a log from given file. Returns an error if the file could not be read or parsed.
+    pub fn try_from_file(file: &str) -> Result<InputLog, Error> {
+        let mut json = String::new();
+        File::open(file)?.read_to_string(&mut json)?;
+        Self::try_from_json(&json)
+    }
+
+    /// Loads a log from a JSON string created by [`InputLog::to_json()`](#method.to_json).
+    pub fn try_from_json(json: &str) -> Result<InputLog, Error> {
+        let root: Value = serde_json::from_str(json).map_err(|error| Error::InputLogError(format!("{}", error)))?;
+        let frames = root.find("frames").and_then(Value::as_array).ok_or_else(|| Error::InputLogError("missing frames".to_string()))?;
+        let mut log = InputLog::new();
+        for (frame_id, frame) in frames.iter().enumerate() {
+            log.frames.push(parse_frame(frame).ok_or_else(|| Error::InputLogError(format!("invalid frame {}", frame_id)))?);
+        }
+        Ok(log)
+    }
+
+    /// Returns the log as a JSON string.
+    pub fn to_json(self: &Self) -> String {
+        let mut root = serde_json::Map::new();
+        root.insert("frames".to_string(), Value::Array(self.frames.iter().map(frame_value).collect()));
+        serde_json::to_string(&Value::Object(root)).unwrap()
+    }
+
+    /// Writes the log to given file.
+    pub fn save(self: &Self, file: &str) -> Result<(), Error> {
+        File::create(file)?.write_all(self.to_json().as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the number of recorded frames.
+    pub fn len(self: &Self) -> usize {
+        self.frames.len()
+    }
+}
+
+/// Records the input state of each frame into an [`InputLog`](struct.InputLog.html).
+///
+/// Call [`InputRecorder::record()`](#method.record) once per frame after
+/// [`Display::poll_events()`](struct.Display.html#method.poll_events), passing the loop state
+/// provided by [`utils::mainloop()`](utils/fn.mainloop.html) or
+/// [`utils::renderloop()`](utils/fn.renderloop.html).
+pub struct InputRecorder {
+    input   : Input,
+    log     : Mutex<InputLog>,
+}
+
+impl InputRecorder {
+
+    /// Creates a new recorder for given input.
+    pub fn new(input: &Input) -> InputRecorder {
+        InputRecorder {
+            input   : input.clone(),
+            log     : Mutex::new(InputLog::new()),
+        }
+    }
+
+    /// Records the current input state together with given loop state.
+    pub fn record(self: &Self, loop_state: &LoopState) {
+        self.log.lock().unwrap().frames.push(InputFrame {
+            loop_state  : *loop_state,
+            input_state : input::state(&self.input).clone(),
+        });
+    }
+
+    /// Returns a copy of the frames recorded so far.
+    pub fn log(self: &Self) -> InputLog {
+        self.log.lock().unwrap().clone()
+    }
+
+    /// Removes all recorded frames.
+    pub fn clear(self: &Self) {
+        self.log.lock().unwrap().frames.clear();
+    }
+}
+
+/// Plays back an [`InputLog`](struct.InputLog.html) without a window.
+///
+/// Each call to [`InputReplay::next()`](#method.next) applies the next recorded frame to the
+/// [`Input`](struct.Input.html) returned by [`InputReplay::input()`](#method.input) and returns
+/// the recorded loop state, so that the game can be updated with the recorded frame deltas.
+pub struct InputReplay {
+    input_state : Arc<RwLock<InputState>>,
+    log         : InputLog,
+    position    : AtomicUsize,
+}
+
+impl InputReplay {
+
+    /// Creates a new replay of given log.
+    pub fn new(log: InputLog) -> InputReplay {
+        InputReplay {
+            input_state : Arc::new(RwLock::new(InputState::new())),
+            log         : log,
+            position    : AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns an input that reports the state of the current frame.
+    pub fn input(self: &Self) -> Input {
+        input::from_state(&self.input_state)
+    }
+
+    /// Advances to the next frame and returns its loop state, or None once all frames were played.
+    pub fn next(self: &Self) -> Option<LoopState> {
+        let position = self.position.fetch_add(1, Ordering::SeqCst);
+        self.log.frames.get(position).map(|frame| {
+            let mut input_state = self.input_state.write().unwrap();
+            let dead_zone = input_state.dead_zone;
+            *input_state = frame.input_state.clone();
+            input_state.dead_zone = dead_zone;
+            frame.loop_state
+        })
+    }
+
+    /// Returns true once all frames were played.
+    pub fn is_finished(self: &Self) -> bool {
+        self.position.load(Ordering::SeqCst) >= self.log.frames.len()
+    }
+}
+
+/// Serializes a recorded frame.
+fn frame_value(frame: &InputFrame) -> Value {
+
+    let loop_state = &frame.loop_state;
+    let input_state = &frame.input_state;
+    let mut result = serde_json::Map::new();
+
+    result.insert("delta".to_string(), Value::U64(nanos(loop_state.delta)));
+    result.insert("elapsed".to_string(), Value::U64(nanos(loop_state.elapsed)));
+    result.insert("frame_id".to_string(), Value::U64(loop_state.frame_id));
+    result.insert("fps".to_string(), Value::U64(loop_state.fps as u64));
+    result.insert("state_id".to_string(), Value::U64(loop_state.state_id as u64));
+    result.insert("mouse".to_string(), Value::Array(vec![ Value::I64(input_state.mouse.0 as i64), Value::I64(input_state.mouse.1 as i64) ]));
+    result.insert("mouse_delta".to_string(), Value::Array(vec![ Value::I64(input_state.mouse_delta.0 as i64), Value::I64(input_state.mouse_delta.1 as i64) ]));
+    result.insert("mouse_inside".to_string(), Value::Bool(input_state.mouse_inside));
+    result.insert("wheel_lines".to_string(), Value::Array(vec![ Value::F64(input_state.wheel_lines.0 as f64), Value::F64(input_state.wheel_lines.1 as f64) ]));
+    result.insert("wheel_pixels".to_string(), Value::Array(vec![ Value::F64(input_state.wheel_pixels.0 as f64), Value::F64(input_state.wheel_pixels.1 as f64) ]));
+    result.insert("dimensions".to_string(), Value::Array(vec![ Value::U64(input_state.dimensions.0 as u64), Value::U64(input_state.dimensions.1 as u64) ]));
+    result.insert("key".to_string(), buttons_value(&input_state.key));
+    result.insert("button".to_string(), buttons_value(&input_state.button));
+
+    result.insert("text".to_string(), Value::Array(input_state.text.iter().map(|event| Value::String(match *event {
+        TextEvent::Char(character) => character.to_string(),
+        TextEvent::Backspace => "\u{8}".to_string(),
+        TextEvent::Delete => "\u{7f}".to_string(),
+        TextEvent::Enter => "\r".to_string(),
+    })).collect()));
+
+    let mut gamepads = serde_json::Map::new();
+    for (gamepad_id, gamepad) in input_state.gamepads.iter() {
+        let mut value = serde_json::Map::new();
+        value.insert("name".to_string(), Value::String(gamepad.name.clone()));
+        value.insert("buttons".to_string(), Value::Array(gamepad.buttons.iter().map(|&state| Value::U64(state_id(state))).collect()));
+        value.insert("axes".to_string(), Value::Array(gamepad.axes.iter().map(|&axis| Value::F64(axis as f64)).collect()));
+        gamepads.insert(gamepad_id.to_string(), Value::Object(value));
+    }
+    result.insert("gamepads".to_string(), Value::Object(gamepads));
+
+    result.insert("gamepad_events".to_string(), Value::Array(input_state.gamepad_events.iter().map(|event| Value::Array(match *event {
+        GamepadEvent::Connected(gamepad_id, ref name) => vec![ Value::String("connected".to_string()), Value::U64(gamepad_id as u64), Value::String(name.clone()) ],
+        GamepadEvent::Disconnected(gamepad_id) => vec![ Value::String("disconnected".to_string()), Value::U64(gamepad_id as u64) ],
+        GamepadEvent::Button(gamepad_id, button, pressed) => vec![ Value::String("button".to_string()), Value::U64(gamepad_id as u64), Value::U64(button as u64), Value::Bool(pressed) ],
+        GamepadEvent::Axis(gamepad_id, axis, position) => vec![ Value::String("axis".to_string()), Value::U64(gamepad_id as u64), Value::U64(axis as u64), Value::F64(position as f64) ],
+    })).collect()));
+
+    Value::Object(result)
+}
+
+/// Deserializes a recorded frame.
+fn parse_frame(value: &Value) -> Option<InputFrame> {
+
+    let delta = duration(value.find("delta")?.as_u64()?);
+    let elapsed = duration(value.find("elapsed")?.as_u64()?);
+
+    let loop_state = LoopState {
+        delta       : delta,
+        delta_f32   : seconds(delta),
+        elapsed     : elapsed,
+        elapsed_f32 : seconds(elapsed),
+        frame_id    : value.find("frame_id")?.as_u64()?,
+        fps         : value.find("fps")?.as_u64()? as u32,
+        state_id    : value.find("state_id")?.as_u64()? as u32,
+    };
+
+    let mut input_state = InputState::new();
+    let (x, y) = pair(value.find("mouse")?, Value::as_i64)?;
+    input_state.mouse = (x as i32, y as i32);
+    let (x, y) = pair(value.find("mouse_delta")?, Value::as_i64)?;
+    input_state.mouse_delta = (x as i32, y as i32);
+    input_state.mouse_inside = value.find("mouse_inside")?.as_bool()?;
+    let (x, y) = pair(value.find("wheel_lines")?, Value::as_f64)?;
+    input_state.wheel_lines = (x as f32, y as f32);
+    let (x, y) = pair(value.find("wheel_pixels")?, Value::as_f64)?;
+    input_state.wheel_pixels = (x as f32, y as f32);
+    let (width, height) = pair(value.find("dimensions")?, Value::as_u64)?;
+    input_state.dimensions = (width as u32, height as u32);
+    parse_buttons(value.find("key")?, &mut input_state.key)?;
+    parse_buttons(value.find("button")?, &mut input_state.button)?;
+
+    for text in value.find("text")?.as_array()? {
+        for character in text.as_str()?.chars() {
+            input_state.text.push(input::text_event(character)?);
+        }
+    }
+
+    for (gamepad_id, gamepad) in value.find("gamepads")?.as_object()? {
+        let mut state = GamepadState::new(gamepad.find("name")?.as_str()?.to_string());
+        for button in gamepad.find("buttons")?.as_array()? {
+            state.buttons.push(button_state(button.as_u64()?)?);
+        }
+        for axis in gamepad.find("axes")?.as_array()? {
+            state.axes.push(axis.as_f64()? as f32);
+        }
+        input_state.gamepads.insert(gamepad_id.parse().ok()?, state);
+    }
+
+    for event in value.find("gamepad_events")?.as_array()? {
+        let event = event.as_array()?;
+        let number = |index: usize| event.get(index).and_then(Value::as_u64);
+        input_state.gamepad_events.push(match event.get(0).and_then(Value::as_str)? {
+            "connected" => GamepadEvent::Connected(number(1)? as usize, event.get(2)?.as_str()?.to_string()),
+            "disconnected" => GamepadEvent::Disconnected(number(1)? as usize),
+            "button" => GamepadEvent::Button(number(1)? as usize, number(2)? as u32, event.get(3)?.as_bool()?),
+            "axis" => GamepadEvent::Axis(number(1)? as usize, number(2)? as u32, event.get(3)?.as_f64()? as f32),
+            _ => return None,
+        });
+    }
+
+    Some(InputFrame {
+        loop_state  : loop_state,
+        input_state : input_state,
+    })
+}
+
+/// Serializes all keys or buttons that are not Up as [ index, state ] pairs.
+fn buttons_value(states: &[ButtonState]) -> Value {
+    Value::Array(states.iter().enumerate().filter(|&(_, &state)| state != ButtonState::Up).map(|(index, &state)| {
+        Value::Array(vec![ Value::U64(index as u64), Value::U64(state_id(state)) ])
+    }).collect())
+}
+
+/// Deserializes keys or buttons serialized by buttons_value().
+fn parse_buttons(value: &Value, states: &mut [ButtonState]) -> Option<()> {
+    for entry in value.as_array()? {
+        let (index, state) = pair(entry, Value::as_u64)?;
+        *states.get_mut(index as usize)? = button_state(state)?;
+    }
+    Some(())
+}
+
+/// Returns the serialized id of a button state.
+fn state_id(state: ButtonState) -> u64 {
+    match state {
+        ButtonState::Up => 0,
+        ButtonState::Pressed => 1,
+        ButtonState::Down => 2,
+        ButtonState::Released => 3,
+    }
+}
+
+/// Returns the button state for given serialized id.
+fn button_state(state_id: u64) -> Option<ButtonState> {
+    match state_id {
+        0 => Some(ButtonState::Up),
+        1 => Some(ButtonState::Pressed),
+        2 => Some(ButtonState::Down),
+        3 => Some(ButtonState::Released),
+        _ => None,
+    }
+}
+
+/// Reads a two element array.
+fn pair<T, F>(value: &Value, convert: F) -> Option<(T, T)> where F: Fn(&Value) -> Option<T> {
+    let array = value.as_array()?;
+    if array.len() == 2 {
+        Some((convert(&array[0])?, convert(&array[1])?))
+    } else {
+        None
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1000000000 + duration.subsec_nanos() as u64
+}
+
+fn duration(nanos: u64) -> Duration {
+    Duration::new(nanos / 1000000000, (nanos % 1000000000) as u32)
+}
+
+fn seconds(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + (duration.subsec_nanos() as f64 / 1000000000.0) as f32
+}
@@ -1,10 +1,13 @@
 use glium;
-use core::{display, Display, font};
+use core::{display, Display, font, atlas, program, scaling, Error};
 use prelude::*;
 use std::borrow::Cow;
 
-// Number of texture buckets. Also requires change to renderer.rs at "let uniforms = uniform! { ... }"
-pub const NUM_BUCKETS: usize = 6;
+// Bucket ids determine which texture a vertex samples from. Also requires change to default.fs.
+pub const FONT_BUCKET: u32 = 0;
+pub const ATLAS_BUCKET: u32 = 1;
+pub const TEXTURE_BUCKET: u32 = 2;
+pub const COLOR_BUCKET: u32 = 3;
 
 // Initial sprite capacity. Automatically increases.
 pub const INITIAL_CAPACITY: usize = 512;
@@ -23,6 +26,10 @@ pub fn new(data: RenderContextData) -> RenderContext {
 pub fn lock<'b>(context: &'b RenderContext) -> MutexGuard<'b, RenderContextData> {
     context.0.lock().unwrap()
 }
+/// Locks the context, even if a thread panicked while holding the lock.
+pub fn lock_poisoned<'b>(context: &'b RenderContext) -> MutexGuard<'b, RenderContextData> {
+    context.0.lock().unwrap_or_else(|error| error.into_inner())
+}
 
 /// Individual Texture
 #[derive(Clone)]
@@ -44,29 +51,16 @@ impl<'a> glium::texture::Texture2dDataSource<'a> for RenderContextTexture {
     }
 }
 
-/// Texture data for a single texture array
-pub struct RenderContextTextureArray {
-    pub dirty   : bool,
-    pub data    : glium::texture::SrgbTexture2dArray,
-    pub raw     : Vec<RenderContextTexture>,
-}
-
-impl RenderContextTextureArray {
-    pub fn new(display: &Display) -> Self {
-        RenderContextTextureArray {
-            dirty   : false,
-            data    : glium::texture::SrgbTexture2dArray::empty(display::handle(&display), 2, 2, 1).unwrap(),
-            raw     : Vec::new(),
-        }
-    }
-}
-
 /// Internal data of a RenderContext
 pub struct RenderContextData {
-    pub index_buffer    : glium::IndexBuffer<u32>,
     pub program         : glium::Program,
-    pub tex_array       : Vec<RenderContextTextureArray>,
+    pub atlas           : atlas::Atlas,
     pub target          : Option<glium::Frame>,
+    pub texture_target  : Option<usize>,
+    pub textures        : HashMap<usize, glium::texture::Texture2d>,
+    pub programs        : HashMap<usize, program::ProgramData>,
+    pub scaling         : Option<scaling::Scaling>,
+    pub scaling_override: Option<bool>,
     pub display         : Display,
     pub font_cache      : font::FontCache,
     pub font_texture    : glium::texture::Texture2d,
@@ -75,23 +69,21 @@ pub struct RenderContextData {
 impl RenderContextData {
 
     /// Create a new instance
-    pub fn new(display: &Display, initial_capacity: usize) -> Self {
-
-        let mut tex_array = Vec::new();
-
-        for _ in 0..NUM_BUCKETS {
-            tex_array.push(RenderContextTextureArray::new(display));
-        }
+    pub fn new(display: &Display) -> Result<Self, Error> {
 
-        RenderContextData {
-            index_buffer    : Self::create_index_buffer(&display::handle(&display), initial_capacity),
-            program         : Self::create_program(&display::handle(&display)),
-            tex_array       : tex_array,
+        Ok(RenderContextData {
+            program         : Self::create_program(&display::handle(&display))?,
+            atlas           : atlas::Atlas::new(display),
             target          : Option::None,
+            texture_target  : Option::None,
+            textures        : HashMap::new(),
+            programs        : HashMap::new(),
+            scaling         : None,
+            scaling_override: None,
             display         : display.clone(),
             font_cache      : font::FontCache::new(512, 512, 0.01, 0.01),
             font_texture    : font::create_cache_texture(&display::handle(&display), 512, 512),
-        }
+        })
     }
 
     /// Update font-texture from cache
@@ -99,62 +91,30 @@ impl RenderContextData {
         self.font_cache.update(&mut self.font_texture);
     }
 
-    /// Update texture arrays from registered textures
-    pub fn update_tex_array(self: &mut Self) {
-        for ref mut array in self.tex_array.iter_mut() {
-            if array.dirty {
-                array.dirty = false;
-                if array.raw.len() > 0 {
-                    array.data = glium::texture::SrgbTexture2dArray::new(display::handle(&self.display), array.raw.clone()).unwrap();
-                } else {
-                    array.data = glium::texture::SrgbTexture2dArray::empty(display::handle(&self.display), 2, 2, 1).unwrap();
-                }
-            }
-        }
-    }
-
-    /// Update index buffer to given size
-    pub fn update_index_buffer(self: &mut Self, max_sprites: usize) {
-        if max_sprites * 6 > self.index_buffer.len() {
-            self.index_buffer = Self::create_index_buffer(&display::handle(&self.display), max_sprites);
-        }
+    /// Update atlas texture array from registered frames
+    pub fn update_atlas(self: &mut Self) {
+        self.atlas.update(&self.display);
     }
 
-    /// Store given frames to texture arrays
-    pub fn store_frames<'a>(self: &mut Self, bucket_id: u32, raw_frames: Vec<RenderContextTexture>) -> u32 {
-        let texture_id = self.tex_array[bucket_id as usize].raw.len() as u32;
-        for frame in raw_frames {
-            self.tex_array[bucket_id as usize].raw.push(frame);
-        }
-        self.tex_array[bucket_id as usize].dirty = true;
-        texture_id
+    /// Store given frames to the atlas and return their locations. On error, no frames are stored.
+    pub fn store_frames(self: &mut Self, raw_frames: Vec<RenderContextTexture>) -> Result<Vec<atlas::AtlasFrame>, Error> {
+        self.atlas.insert_all(&raw_frames)
     }
 
-    /// creates vertex pool for given number of sprites
-    fn create_index_buffer(display: &glium::Display, max_sprites: usize) -> glium::index::IndexBuffer<u32> {
-
-        let mut ib_data = Vec::with_capacity(max_sprites as usize * 6);
-
-        for i in 0..max_sprites {
-            let num = i as u32;
-            ib_data.push(num * 4);
-            ib_data.push(num * 4 + 1);
-            ib_data.push(num * 4 + 2);
-            ib_data.push(num * 4 + 1);
-            ib_data.push(num * 4 + 3);
-            ib_data.push(num * 4 + 2);
+    /// Release the atlas space of given frames
+    pub fn release_frames(self: &mut Self, frames: &[atlas::AtlasFrame]) {
+        for frame in frames {
+            self.atlas.release(frame);
         }
-
-        glium::index::IndexBuffer::new(display, glium::index::PrimitiveType::TrianglesList, &ib_data).unwrap()
     }
 
     /// creates the shader program
-    fn create_program(display: &glium::Display) -> glium::Program {
+    fn create_program(display: &glium::Display) -> Result<glium::Program, Error> {
         program!(display,
             140 => {
                 vertex: include_str!("../shader/default.vs"),
                 fragment: include_str!("../shader/default.fs")
             }
-        ).unwrap()
+        ).map_err(|error| Error::ShaderError(format!("{:?}", error)))
     }
 }
@@ -1,7 +1,9 @@
 use prelude::*;
 use glium;
+use image;
 use glium::Surface;
-use core::{Display, rendercontext, RenderContext, RenderContextData, layer, Layer, blendmode, scene, Color, display};
+use maths::{Mat4, Vec2};
+use core::{Display, Error, scaling, ScalingPolicy, rendercontext, RenderContext, RenderContextData, layer, Layer, texture, Texture, program, postprocessor, PostProcessor, blendmode, scene, tilemap, Tilemap, Color, display};
 
 /// A renderer is used to render [`Layer`](struct.Layer.html)s or [`Scene`](struct.Scene.html)s to the
 /// [`Display`](struct.Display.html).
@@ -21,12 +23,17 @@ impl<'a> Renderer {
 
     /// Returns a new renderer instance.
     pub fn new(display: &Display) -> Self {
+        Self::try_new(display).unwrap()
+    }
+
+    /// Returns a new renderer instance or an error if the renderer's shaders could not be compiled.
+    pub fn try_new(display: &Display) -> Result<Self, Error> {
 
-        let context_data = RenderContextData::new(display, rendercontext::INITIAL_CAPACITY);
+        let context_data = RenderContextData::new(display)?;
 
-        Renderer {
+        Ok(Renderer {
             context: rendercontext::new(context_data),
-        }
+        })
     }
 
     /// Returns a reference to the renderers' context. The [`RenderContext`](struct.RenderContext)
@@ -51,6 +58,167 @@ impl<'a> Renderer {
         context.target = Some(target);
     }
 
+    /// Redirects all layer drawing performed by given function into the given texture.
+    ///
+    /// Once the function returns, drawing resumes on the previous target. The texture can then
+    /// be drawn onto other layers using [`Texture::draw()`](struct.Texture.html#method.draw).
+    /// Sprites showing the texture itself are skipped while drawing into it.
+    pub fn render_to<F>(&self, texture: &Texture, mut draw_func: F) -> &Self where F: FnMut() {
+        let previous = {
+            let mut context = rendercontext::lock(&self.context);
+            mem::replace(&mut context.texture_target, Some(texture::id(texture)))
+        };
+        // restore the previous target even if the function panics
+        let _guard = TargetGuard { context: &self.context, previous: previous };
+        draw_func();
+        self
+    }
+
+    /// Draws the layers drawn by given function into an intermediate texture, applies the
+    /// post processor's effects to it and draws the result onto the current target.
+    ///
+    /// The target must be prepared (e.g. using [`clear_target()`](#method.clear_target)) before
+    /// calling this function.
+    pub fn postprocess<F>(&self, postprocessor: &PostProcessor, draw_func: F) -> &Self where F: FnMut() {
+        let dimensions = self.target_dimensions();
+        postprocessor::process(postprocessor, self, dimensions, draw_func);
+        self
+    }
+
+    /// Sets a virtual resolution for drawing onto the display. Layers created with the virtual
+    /// dimensions are fitted into the window according to given policy whenever the window size
+    /// changes. Drawing onto textures is not affected.
+    pub fn set_scaling(&self, width: u32, height: u32, policy: ScalingPolicy) -> &Self {
+        rendercontext::lock(&self.context).scaling = Some(scaling::Scaling {
+            width   : width as f32,
+            height  : height as f32,
+            policy  : policy,
+        });
+        self
+    }
+
+    /// Removes the virtual resolution. Layers are mapped to the full window again.
+    pub fn clear_scaling(&self) -> &Self {
+        rendercontext::lock(&self.context).scaling = None;
+        self
+    }
+
+    /// Converts window coordinates, e.g. from [`Input::mouse()`](struct.Input.html#method.mouse),
+    /// to virtual coordinates. Without a virtual resolution, the coordinates are returned unchanged.
+    ///
+    /// For layers using the default view matrix, virtual coordinates are layer coordinates.
+    pub fn window_to_virtual(&self, position: (i32, i32)) -> (f32, f32) {
+        let context = rendercontext::lock(&self.context);
+        let position = (position.0 as f32, position.1 as f32);
+        match context.scaling {
+            Some(scaling) => scaling.to_virtual(window_dimensions(&context), position),
+            None => position,
+        }
+    }
+
+    /// Converts window coordinates to coordinates on given layer, taking the layer's view matrix
+    /// (e.g. a camera) into account. The layer's model matrix is not applied.
+    ///
+    /// Returns `None` if the view matrix cannot be inverted.
+    pub fn window_to_layer(&self, layer: &Layer, position: (i32, i32)) -> Option<(f32, f32)> {
+        let (x, y) = self.window_to_virtual(position);
+        let (width, height) = {
+            let context = rendercontext::lock(&self.context);
+            match context.scaling {
+                Some(scaling) => (scaling.width, scaling.height),
+                None => {
+                    let window = window_dimensions(&context);
+                    (window.0 as f32, window.1 as f32)
+                }
+            }
+        };
+        let clip = Mat4::viewport(width, height).transform(Vec2(x, y));
+        let view_matrix = *layer.view_matrix().deref_mut();
+        view_matrix.untransform(clip).map(|point| (point.0, point.1))
+    }
+
+    /// Returns the dimensions of the current target.
+    fn target_dimensions(&self) -> (u32, u32) {
+        let context = rendercontext::lock(&self.context);
+        if let Some(target_id) = context.texture_target {
+            context.textures[&target_id].as_surface().get_dimensions()
+        } else {
+            context.target.as_ref().expect("no target prepared").get_dimensions()
+        }
+    }
+
+    /// Reads back the contents of the current target. Returns width, height and RGBA pixel data
+    /// with rows ordered from top to bottom.
+    ///
+    /// This is slow and should not be done every frame. It is intended for tests, e.g. in
+    /// combination with [`Display::headless()`](struct.Display.html#method.headless).
+    pub fn read_target(&self) -> (u32, u32, Vec<u8>) {
+        self.try_read_target().unwrap()
+    }
+
+    /// Reads back the contents of the current target. Returns an error instead of panicking if
+    /// no target was prepared or the target could not be read.
+    /// See [`read_target()`](#method.read_target).
+    pub fn try_read_target(&self) -> Result<(u32, u32, Vec<u8>), Error> {
+
+        let context = rendercontext::lock(&self.context);
+
+        let raw: glium::texture::RawImage2d<u8> = if let Some(target_id) = context.texture_target {
+            context.textures.get(&target_id).ok_or_else(|| Error::GlError("render target no longer exists".to_string()))?.read()
+        } else {
+            let target = context.target.as_ref().ok_or_else(|| Error::GlError("no target prepared".to_string()))?;
+            let (width, height) = target.get_dimensions();
+            let texture = glium::texture::Texture2d::empty_with_format(
+                display::handle(&context.display),
+                glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                glium::texture::MipmapsOption::NoMipmap,
+                width,
+                height
+            ).map_err(|error| Error::GlError(format!("{:?}", error)))?;
+            target.fill(&texture.as_surface(), glium::uniforms::MagnifySamplerFilter::Nearest);
+            texture.read()
+        };
+
+        // opengl stores rows bottom to top
+
+        let (width, height) = (raw.width, raw.height);
+        let row_size = width as usize * 4;
+        let mut data = Vec::with_capacity(row_size * height as usize);
+
+        if raw.data.len() < row_size * height as usize {
+            return Err(Error::GlError(format!("read {} bytes for a {}x{} target", raw.data.len(), width, height)));
+        }
+
+        for row in (0..height as usize).rev() {
+            data.extend_from_slice(&raw.data[row * row_size .. (row + 1) * row_size]);
+        }
+
+        Ok((width, height, data))
+    }
+
+    /// Captures the contents of the current target as an image.
+    ///
+    /// Like [`read_target()`](#method.read_target), this should be called before
+    /// [`swap_target()`](#method.swap_target).
+    pub fn capture(&self) -> image::RgbaImage {
+        self.try_capture().unwrap()
+    }
+
+    /// Captures the contents of the current target as an image. Returns an error instead of
+    /// panicking. See [`capture()`](#method.capture).
+    pub fn try_capture(&self) -> Result<image::RgbaImage, Error> {
+        let (width, height, data) = self.try_read_target()?;
+        image::ImageBuffer::from_raw(width, height, data).ok_or_else(|| Error::GlError(format!("invalid image data for {}x{} pixels", width, height)))
+    }
+
+    /// Captures the contents of the current target and saves them as a PNG file.
+    pub fn capture_to_file(&self, file: &str) -> Result<(), Error> {
+        let image = image::DynamicImage::ImageRgba8(self.try_capture()?);
+        let mut file = File::create(Path::new(file))?;
+        image.save(&mut file, image::ImageFormat::PNG)?;
+        Ok(())
+    }
+
     /// Finishes drawing and swaps the drawing target to front.
     pub fn swap_target(&self) {
         let mut context = rendercontext::lock(&self.context);
@@ -69,6 +237,13 @@ impl<'a> Renderer {
         self
     }
 
+    /// Draws the visible parts of given tilemap using the view matrix, blendmode, color and program
+    /// of given layer. The contents of the layer are not drawn.
+    pub fn draw_tilemap(&self, tilemap: &Tilemap, layer: &Layer) -> &Self {
+        tilemap::draw(tilemap, self, layer);
+        self
+    }
+
     /// Draws given layer.
     pub fn draw_layer(&self, layer: &Layer) -> &Self {
 
@@ -79,53 +254,129 @@ impl<'a> Renderer {
 
         // update sprite texture arrays, font texture and vertex buffer as required
 
-        context.update_tex_array();
+        context.update_atlas();
         context.update_font_cache();
-        let (vertex_buffer, num_vertices) = layer::upload(&layer, context);
-        context.update_index_buffer(num_vertices / 4);
+        let (vertex_buffer, index_buffer, num_indices) = layer::upload(&layer, context);
 
         // draw the layer, unless it is empty
 
-        if num_vertices > 0 {
-
-            // set up uniforms
-
-            let uniforms = uniform! {
-                view_matrix     : *layer.view_matrix().deref_mut(),
-                model_matrix    : *layer.model_matrix().deref_mut(),
-                global_color    : *layer.color().deref_mut(),
-                font_cache      : context.font_texture.sampled().magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                tex1            : &context.tex_array[1].data,   // 32
-                tex2            : &context.tex_array[2].data,   // 64
-                tex3            : &context.tex_array[3].data,   // 128
-                tex4            : &context.tex_array[4].data,   // 256
-                tex5            : &context.tex_array[5].data,   // 512
-            };
+        if num_indices > 0 {
 
             // set up draw parameters for given blend options
 
-            let draw_parameters = glium::draw_parameters::DrawParameters {
+            let mut draw_parameters = glium::draw_parameters::DrawParameters {
                 backface_culling: glium::draw_parameters::BackfaceCullingMode::CullingDisabled,
                 blend           : blendmode::access_blendmode(layer.blendmode().deref_mut()),
                 .. Default::default()
             };
 
-            // draw up to container.size
+            let (view_matrix, scissor) = view_transform(context, layer);
+            draw_parameters.scissor = scissor;
+
+            let model_matrix = *layer.model_matrix().deref_mut();
+            let global_color = *layer.color().deref_mut();
+
+            // use the layer's custom program, if it still exists
+
+            let programs = &context.programs;
+            let custom_program = layer::program_id(layer).and_then(|program_id| programs.get(&program_id));
+            let program = custom_program.map_or(&context.program, |custom_program| &custom_program.program);
+
+            // draw in batches, each with a single render-texture bound
+
+            for (start, end, texture_id) in layer::batches(layer, num_indices) {
+
+                // sampling the texture that is currently being drawn to is undefined
+
+                if texture_id.is_some() && texture_id.map(|texture_id| texture_id as usize) == context.texture_target {
+                    continue;
+                }
+
+                let render_texture = match texture_id {
+                    Some(texture_id) => context.textures.get(&(texture_id as usize)).unwrap_or(&context.font_texture),
+                    None => &context.font_texture,
+                };
+
+                // set up uniforms
+
+                let uniforms = program::ProgramUniforms::new(uniform! {
+                    view_matrix     : view_matrix,
+                    model_matrix    : model_matrix,
+                    global_color    : global_color,
+                    font_cache      : context.font_texture.sampled().magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+                    atlas           : &context.atlas.texture,
+                    render_texture  : render_texture,
+                }, custom_program, &context.textures);
+
+                // draw batch onto current target
 
-            let ib_slice = context.index_buffer.slice(0..num_vertices as usize / 4 * 6).unwrap();
-            context.target.as_mut().unwrap().draw(vertex_buffer.as_ref().unwrap(), &ib_slice, &context.program, &uniforms, &draw_parameters).unwrap();
+                let ib_slice = index_buffer.as_ref().unwrap().slice(start .. end).unwrap();
+
+                if let Some(target_id) = context.texture_target {
+                    if let Some(target_texture) = context.textures.get(&target_id) {
+                        target_texture.as_surface().draw(vertex_buffer.as_ref().unwrap(), &ib_slice, program, &uniforms, &draw_parameters).unwrap();
+                    }
+                } else {
+                    context.target.as_mut().unwrap().draw(vertex_buffer.as_ref().unwrap(), &ib_slice, program, &uniforms, &draw_parameters).unwrap();
+                }
+            }
         }
 
         self
     }
 }
 
-/// returns the appropriate bucket_id and padded texture size for the given texture size
-pub fn bucket_info(width: u32, height: u32) -> (u32, u32) {
-    let ln2 = (cmp::max(width, height) as f32).log2().ceil() as u32;
-    let size = 2u32.pow(ln2);
-    // skip first five sizes 1x1 to 16x16, use id 0 for font-cache
-    let bucket_id = cmp::max(0, ln2 - 4 + 1);
-    assert!(bucket_id < rendercontext::NUM_BUCKETS as u32, "texture size exceeded configured maximum");
-    (bucket_id, size)
+/// Restores the previous texture target when dropped.
+struct TargetGuard<'a> {
+    context     : &'a RenderContext,
+    previous    : Option<usize>,
+}
+
+impl<'a> Drop for TargetGuard<'a> {
+    fn drop(self: &mut Self) {
+        // the drawing function may have panicked while holding the lock
+        rendercontext::lock_poisoned(self.context).texture_target = self.previous;
+    }
+}
+
+/// Returns the view matrix of given layer with the virtual resolution fitted into the current
+/// target (if enabled) and the scissor rectangle required by the scaling policy.
+fn view_transform(context: &RenderContextData, layer: &Layer) -> (Mat4<f32>, Option<glium::Rect>) {
+
+    let view_matrix = *layer.view_matrix().deref_mut();
+
+    // fit the virtual resolution into the target, by default only when drawing onto the display
+
+    if let Some(scaling) = context.scaling {
+        if context.scaling_override.unwrap_or(context.texture_target.is_none()) {
+            let dimensions = match context.texture_target {
+                Some(target_id) => context.textures.get(&target_id).map(|texture| texture.as_surface().get_dimensions()),
+                None => context.target.as_ref().map(|target| target.get_dimensions()),
+            };
+            if let Some(dimensions) = dimensions {
+                let (matrix, scissor) = scaling.transform(dimensions);
+                return (matrix * view_matrix, scissor);
+            }
+        }
+    }
+
+    (view_matrix, None)
+}
+
+/// Returns the window size reported along with the mouse coordinates. The framebuffer size
+/// differs on HiDPI displays and is only used until the window reported its size.
+fn window_dimensions(context: &RenderContextData) -> (u32, u32) {
+    let window = display::input_state(&context.display).read().unwrap().dimensions;
+    if window.0 > 0 && window.1 > 0 { window } else { display::handle(&context.display).get_framebuffer_dimensions() }
+}
+
+/// Returns the view matrix given layer would currently be drawn with, including scaling.
+pub fn view_matrix(renderer: &Renderer, layer: &Layer) -> Mat4<f32> {
+    view_transform(rendercontext::lock(&renderer.context).deref_mut(), layer).0
+}
+
+/// Forces scaling on (Some(true)) or off (Some(false)) regardless of the current target, or
+/// restores the default (None). Returns the previous setting.
+pub fn override_scaling(renderer: &Renderer, scaling_override: Option<bool>) -> Option<bool> {
+    mem::replace(&mut rendercontext::lock(&renderer.context).scaling_override, scaling_override)
 }
@@ -0,0 +1,84 @@
+use glium;
+use maths::{Mat4, Vec3};
+
+/// Determines how content is fitted into the window when its size differs from the virtual
+/// resolution set using [`Renderer::set_scaling()`](struct.Renderer.html#method.set_scaling).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ScalingPolicy {
+    /// Stretches the virtual area to fill the window, ignoring its aspect ratio.
+    Stretch,
+    /// Scales the virtual area as large as possible while preserving its aspect ratio. Unused
+    /// parts of the window are left empty (letterbox or pillarbox).
+    Letterbox,
+    /// Like `Letterbox`, but only scales by integer factors to keep pixels sharp. If the window is
+    /// smaller than the virtual area, it is scaled down like `Letterbox`.
+    PixelPerfect,
+    /// Like `Letterbox`, but instead of leaving parts of the window empty, more of the scene
+    /// becomes visible around the centered virtual area.
+    Expand,
+}
+
+/// A virtual resolution and scaling policy.
+#[derive(Copy, Clone)]
+pub struct Scaling {
+    pub width   : f32,
+    pub height  : f32,
+    pub policy  : ScalingPolicy,
+}
+
+impl Scaling {
+
+    /// Returns the horizontal and vertical scale factors and the top left position of the
+    /// virtual area within a target of given dimensions.
+    fn fit(self: &Self, target: (u32, u32)) -> ((f32, f32), (f32, f32)) {
+
+        let (target_width, target_height) = (target.0 as f32, target.1 as f32);
+        let fit = (target_width / self.width).min(target_height / self.height);
+
+        let scale = match self.policy {
+            ScalingPolicy::Stretch => (target_width / self.width, target_height / self.height),
+            ScalingPolicy::Letterbox | ScalingPolicy::Expand => (fit, fit),
+            ScalingPolicy::PixelPerfect => {
+                let integer = fit.floor();
+                if integer >= 1.0 { (integer, integer) } else { (fit, fit) }
+            },
+        };
+
+        let offset = ((target_width - self.width * scale.0) / 2.0, (target_height - self.height * scale.1) / 2.0);
+        (scale, offset)
+    }
+
+    /// Returns the matrix to be applied after a layer's view matrix and the scissor rectangle
+    /// that clips drawing to the virtual area, if required.
+    pub fn transform(self: &Self, target: (u32, u32)) -> (Mat4<f32>, Option<glium::Rect>) {
+
+        let ((scale_x, scale_y), (left, top)) = self.fit(target);
+
+        // layers map the virtual area to the full target, shrink it to the fitted size
+
+        let mut matrix = Mat4::identity();
+        matrix.scale(Vec3(self.width * scale_x / target.0 as f32, self.height * scale_y / target.1 as f32, 1.0));
+
+        let scissor = match self.policy {
+            ScalingPolicy::Expand | ScalingPolicy::Stretch => None,
+            ScalingPolicy::Letterbox | ScalingPolicy::PixelPerfect => {
+                let width = (self.width * scale_x).round() as u32;
+                let height = (self.height * scale_y).round() as u32;
+                Some(glium::Rect {
+                    left    : left.max(0.0).round() as u32,
+                    bottom  : (target.1 as f32 - top - height as f32).max(0.0).round() as u32,
+                    width   : width.min(target.0),
+                    height  : height.min(target.1),
+                })
+            },
+        };
+
+        (matrix, scissor)
+    }
+
+    /// Converts window coordinates within a target of given dimensions to virtual coordinates.
+    pub fn to_virtual(self: &Self, target: (u32, u32), position: (f32, f32)) -> (f32, f32) {
+        let ((scale_x, scale_y), (left, top)) = self.fit(target);
+        ((position.0 - left) / scale_x, (position.1 - top) / scale_y)
+    }
+}
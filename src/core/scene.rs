@@ -1,7 +1,7 @@
 use prelude::*;
 use misc::AVec;
 use maths::{Mat4, Vec2};
-use core::{Renderer, RenderContext, Layer, Font, Sprite, Color};
+use core::{Renderer, RenderContext, Layer, Font, Sprite, AnimationPlayer, Color};
 use BlendMode;
 
 /// An operation-id returned from Scene::op.
@@ -122,6 +122,16 @@ impl Scene {
         self
     }
 
+    /// Draws the current frame of an animation player onto given layer.
+    pub fn animation(&self, layer_id: LayerId, sprite_id: SpriteId, player: &AnimationPlayer, x: f32, y: f32, color: Color) -> &Self {
+        self.sprite(layer_id, sprite_id, player.frame_id(), x, y, color)
+    }
+
+    /// Draws the current frame of an animation player with given rotation and scaling onto given layer.
+    pub fn animation_transformed(&self, layer_id: LayerId, sprite_id: SpriteId, player: &AnimationPlayer, x: f32, y: f32, color: Color, rotation: f32, scale_x: f32, scale_y: f32) -> &Self {
+        self.sprite_transformed(layer_id, sprite_id, player.frame_id(), x, y, color, rotation, scale_x, scale_y)
+    }
+
     /// Writes a string onto given layer.
     pub fn write(&self, layer_id: LayerId, font_id: FontId, text: &str, x: f32, y: f32) -> &Self {
         let layers = self.layers.get();
@@ -147,6 +157,13 @@ impl Scene {
         SpriteId(self.sprites.push(sprite))
     }
 
+    /// Creates an animation player with a looping clip for each frame range of a registered sprite.
+    /// See [`AnimationPlayer::from_sprite()`](../struct.AnimationPlayer.html#method.from_sprite).
+    pub fn create_animation_player(self: &Self, sprite_id: SpriteId, default_duration: f32) -> AnimationPlayer {
+        let sprites = self.sprites.get();
+        AnimationPlayer::from_sprite(&sprites[sprite_id.0], default_duration)
+    }
+
     /// Register a font for the scene.
     pub fn register_font(self: &Self, font: Font) -> FontId {
         let insert_position = self.fonts.push(font);
@@ -0,0 +1,184 @@
+use prelude::*;
+use core::{layer, Layer, Point, Color, rendercontext};
+
+// Longest miter, relative to the line thickness, before a joint is cut off.
+const MITER_LIMIT: f32 = 2.0;
+
+/// Primitive shapes. Shapes are drawn untextured in given color and can be mixed freely with
+/// sprites and text on the same layer. Filled shapes are drawn as triangle fans, outlines as
+/// one quad per segment.
+///
+/// The model matrix is applied relative to the center of rectangles, circles and arcs, the
+/// first point of lines and the average of all points of polygons. Angles are given in radians
+/// and increase clockwise, starting to the right.
+impl Layer {
+
+    /// Draws a filled rectangle with its top left corner at x/y.
+    pub fn fill_rect(self: &Self, x: f32, y: f32, width: f32, height: f32, color: Color) -> &Self {
+        let (half_width, half_height) = (width / 2.0, height / 2.0);
+        layer::add_quad(self, Point::new(x + half_width, y + half_height), [
+            Point::new(-half_width, -half_height),
+            Point::new(half_width, -half_height),
+            Point::new(-half_width, half_height),
+            Point::new(half_width, half_height),
+        ], color);
+        self
+    }
+
+    /// Draws the outline of a rectangle with its top left corner at x/y. The outline is centered
+    /// on the rectangle's edges.
+    pub fn draw_rect(self: &Self, x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Color) -> &Self {
+        let center = (x + width / 2.0, y + height / 2.0);
+        stroke(self, center, &[ (x, y), (x + width, y), (x + width, y + height), (x, y + height) ], true, thickness, color);
+        self
+    }
+
+    /// Draws a line with given thickness from x1/y1 to x2/y2.
+    pub fn draw_line(self: &Self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color) -> &Self {
+        stroke(self, (x1, y1), &[ (x1, y1), (x2, y2) ], false, thickness, color);
+        self
+    }
+
+    /// Draws connected lines with given thickness through all points.
+    pub fn draw_lines(self: &Self, points: &[(f32, f32)], thickness: f32, color: Color) -> &Self {
+        if points.len() >= 2 {
+            stroke(self, points[0], points, false, thickness, color);
+        }
+        self
+    }
+
+    /// Draws a filled circle centered at x/y.
+    pub fn fill_circle(self: &Self, x: f32, y: f32, radius: f32, color: Color) -> &Self {
+        let points = arc_points((x, y), radius, 0.0, f32::consts::PI * 2.0, false);
+        fill(self, (x, y), &points, true, color);
+        self
+    }
+
+    /// Draws the outline of a circle centered at x/y. The outline is centered on the circle's radius.
+    pub fn draw_circle(self: &Self, x: f32, y: f32, radius: f32, thickness: f32, color: Color) -> &Self {
+        let points = arc_points((x, y), radius, 0.0, f32::consts::PI * 2.0, false);
+        stroke(self, (x, y), &points, true, thickness, color);
+        self
+    }
+
+    /// Draws a filled circle segment ("pie slice") centered at x/y from start to end angle.
+    pub fn fill_arc(self: &Self, x: f32, y: f32, radius: f32, start: f32, end: f32, color: Color) -> &Self {
+        let points = arc_points((x, y), radius, start, end, true);
+        fill(self, (x, y), &points, false, color);
+        self
+    }
+
+    /// Draws an arc centered at x/y from start to end angle.
+    pub fn draw_arc(self: &Self, x: f32, y: f32, radius: f32, start: f32, end: f32, thickness: f32, color: Color) -> &Self {
+        let points = arc_points((x, y), radius, start, end, true);
+        stroke(self, (x, y), &points, false, thickness, color);
+        self
+    }
+
+    /// Draws a filled convex polygon. Concave polygons are not drawn correctly.
+    pub fn fill_polygon(self: &Self, points: &[(f32, f32)], color: Color) -> &Self {
+        if points.len() >= 3 {
+            let center = centroid(points);
+            fill(self, center, points, true, color);
+        }
+        self
+    }
+
+    /// Draws the outline of a polygon. The outline is centered on the polygon's edges.
+    pub fn draw_polygon(self: &Self, points: &[(f32, f32)], thickness: f32, color: Color) -> &Self {
+        if points.len() >= 2 {
+            stroke(self, centroid(points), points, true, thickness, color);
+        }
+        self
+    }
+}
+
+/// Returns points on the circumference of a circle. For open arcs, the end point is included.
+fn arc_points(center: (f32, f32), radius: f32, start: f32, end: f32, open: bool) -> Vec<(f32, f32)> {
+
+    // aim for segments of about 4 pixels
+
+    let angle = end - start;
+    let circumference = radius.abs() * angle.abs();
+    let segments = cmp::max(cmp::min((circumference / 4.0).ceil() as usize, 256), if open { 1 } else { 8 });
+    let num_points = if open { segments + 1 } else { segments };
+
+    (0..num_points).map(|index| {
+        let current = start + angle * index as f32 / segments as f32;
+        (center.0 + current.cos() * radius, center.1 + current.sin() * radius)
+    }).collect()
+}
+
+/// Returns the average of given points.
+fn centroid(points: &[(f32, f32)]) -> (f32, f32) {
+    let sum = points.iter().fold((0.0, 0.0), |sum, point| (sum.0 + point.0, sum.1 + point.1));
+    (sum.0 / points.len() as f32, sum.1 / points.len() as f32)
+}
+
+/// Draws a triangle fan around pos through all points.
+fn fill(layer: &Layer, pos: (f32, f32), points: &[(f32, f32)], closed: bool, color: Color) {
+
+    let uv = Point::new(0.0, 0.0);
+    let mut vertices = vec![ (Point::new(0.0, 0.0), uv, color) ];
+    vertices.extend(points.iter().map(|point| (Point::new(point.0 - pos.0, point.1 - pos.1), uv, color)));
+
+    // vertex 0 is the center, followed by the points
+
+    let num_points = points.len() as u32;
+    let num_triangles = if closed { num_points } else { num_points - 1 };
+    let indices: Vec<u32> = (0..num_triangles).flat_map(|index| vec![ 0, index + 1, (index + 1) % num_points + 1 ]).collect();
+
+    layer::add_triangles(layer, rendercontext::COLOR_BUCKET, 0, Point::new(pos.0, pos.1), &vertices, &indices);
+}
+
+/// Draws lines through all points, using mitered joints.
+fn stroke(layer: &Layer, pos: (f32, f32), points: &[(f32, f32)], closed: bool, thickness: f32, color: Color) {
+
+    let num_points = points.len();
+    let half = thickness / 2.0;
+
+    // compute the outer and inner edge at each point
+
+    let edges: Vec<(Point, Point)> = (0..num_points).map(|index| {
+        let point = points[index];
+        let previous = if index > 0 { Some(points[index - 1]) } else if closed { Some(points[num_points - 1]) } else { None };
+        let next = if index + 1 < num_points { Some(points[index + 1]) } else if closed { Some(points[0]) } else { None };
+        let (nx, ny) = match (previous.and_then(|previous| normal(previous, point)), next.and_then(|next| normal(point, next))) {
+            (Some(n0), Some(n1)) => {
+                // miter: the average normal, extended so the edges keep their thickness
+                let (mx, my) = normalize((n0.0 + n1.0, n0.1 + n1.1)).unwrap_or(n1);
+                let scale = 1.0 / (mx * n1.0 + my * n1.1).max(1.0 / MITER_LIMIT);
+                (mx * scale, my * scale)
+            },
+            (Some(n), None) | (None, Some(n)) => n,
+            (None, None) => (0.0, 0.0),
+        };
+        (
+            Point::new(point.0 + nx * half - pos.0, point.1 + ny * half - pos.1),
+            Point::new(point.0 - nx * half - pos.0, point.1 - ny * half - pos.1),
+        )
+    }).collect();
+
+    let num_segments = if closed { num_points } else { num_points - 1 };
+
+    for index in 0..num_segments {
+        let (outer0, inner0) = edges[index];
+        let (outer1, inner1) = edges[(index + 1) % num_points];
+        layer::add_quad(layer, Point::new(pos.0, pos.1), [ outer0, outer1, inner0, inner1 ], color);
+    }
+}
+
+/// Returns the unit normal of the line from a to b, or None if both points are equal.
+fn normal(a: (f32, f32), b: (f32, f32)) -> Option<(f32, f32)> {
+    normalize((a.1 - b.1, b.0 - a.0))
+}
+
+/// Returns the normalized vector, or None for zero length vectors.
+fn normalize(vector: (f32, f32)) -> Option<(f32, f32)> {
+    let length = (vector.0 * vector.0 + vector.1 * vector.1).sqrt();
+    if length > 0.0 {
+        Some((vector.0 / length, vector.1 / length))
+    } else {
+        None
+    }
+}
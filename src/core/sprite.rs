@@ -1,5 +1,7 @@
 use prelude::*;
-use core::{renderer, layer, Layer, Point, Rect, rendercontext, RenderContext, RenderContextTexture};
+use core::{layer, Layer, Point, Rect, rendercontext, RenderContext, RenderContextTexture, Error};
+use core::atlas::AtlasFrame;
+use core::metadata::{self, SheetMetadata};
 use Color;
 use image;
 use image::GenericImage;
@@ -9,21 +11,111 @@ use regex::Regex;
 ///
 /// Sprites are created from spritesheets containing one or more frames. To determine frame
 /// dimensions, [`Sprite::from_file()`](#method.from_file) expects sprite sheet file names to
-/// follow a specific pattern. (Future versions will add more configurable means to load sprites.)
+/// follow a specific pattern or a metadata file to be present next to the sprite sheet.
+/// Sprites can also be created from memory using [`Sprite::from_bytes()`](#method.from_bytes),
+/// [`Sprite::from_image()`](#method.from_image) or [`Sprite::from_rgba()`](#method.from_rgba),
+/// which take explicit frame dimensions instead.
+///
+/// # Metadata
+///
+/// Sprite sheet metadata files use the JSON format exported by TexturePacker (hash or array)
+/// and Aseprite. They define the location of each frame within the sheet and optionally
+/// trimming, rotation, pivot points, frame durations and named frame ranges (Aseprite frame
+/// tags or TexturePacker animations).
+///
+/// # Nine-slice
+///
+/// Sprites with border [`insets`](#structfield.insets) can be drawn at arbitrary sizes using
+/// [`Sprite::draw_nine_slice()`](#method.draw_nine_slice), e.g. for scalable UI panels. The
+/// corners keep their size, the edges are stretched along one axis and the center along both.
+/// Insets are read from the center of Aseprite slices if present.
+///
+/// # Atlas space
+///
+/// Frames are stored in a shared texture atlas. Their space is released once the sprite and all
+/// of its clones were dropped, but only becomes available again when all frames on the same atlas
+/// page were released.
 #[derive(Clone)]
 pub struct Sprite {
     /// Defines the sprite origin. Defaults to (0.5, 0.5), meaning that the center of the
     /// sprite would be drawn at the coordinates given to [`Sprite::draw()`](#method.draw). Likewise, (0.0, 0.0)
     /// would mean that the sprite's top left corner would be drawn at the given coordinates.
+    /// Frames with a pivot defined in the sprite sheet metadata ignore this value.
     pub anchor      : (f32, f32),
+    /// Nine-slice border insets (left, top, right, bottom) in pixels, relative to the untrimmed
+    /// frame. Used by [`Sprite::draw_nine_slice()`](#method.draw_nine_slice).
+    pub insets      : Option<(f32, f32, f32, f32)>,
     width           : f32,
     height          : f32,
     num_frames      : u32,
-    bucket_id       : u32,
-    texture_id      : u32,
-    u_max           : f32,
-    v_max           : f32,
-    context         : RenderContext,
+    frames          : Arc<FrameSet>,
+    ranges          : Arc<HashMap<String, (u32, u32)>>,
+}
+
+/// Options for drawing a sprite using [`Sprite::draw_with()`](struct.Sprite.html#method.draw_with).
+///
+/// Flipping, scaling and skewing are applied around the anchor, in this order, followed by the
+/// rotation.
+#[derive(Copy, Clone)]
+pub struct DrawParams {
+    /// Part of the untrimmed frame to draw (x, y, width, height) in pixels, e.g. to crop a
+    /// progress bar. Defaults to the entire frame.
+    pub source      : Option<(f32, f32, f32, f32)>,
+    /// Origin relative to the drawn part of the frame. Defaults to the frame pivot or the
+    /// sprite's [`anchor`](struct.Sprite.html#structfield.anchor).
+    pub anchor      : Option<(f32, f32)>,
+    /// Mirrors the sprite horizontally.
+    pub flip_x      : bool,
+    /// Mirrors the sprite vertically.
+    pub flip_y      : bool,
+    /// Colors of the top left, top right, bottom left and bottom right corner as drawn, before
+    /// rotation. Colors are interpolated in between.
+    pub colors      : [Color; 4],
+    /// Rotation in radians.
+    pub rotation    : f32,
+    pub scale       : (f32, f32),
+    /// Horizontal and vertical skew angle in radians.
+    pub skew        : (f32, f32),
+}
+
+impl Default for DrawParams {
+    fn default() -> DrawParams {
+        DrawParams {
+            source      : None,
+            anchor      : None,
+            flip_x      : false,
+            flip_y      : false,
+            colors      : [ Color::white(); 4 ],
+            rotation    : 0.0,
+            scale       : (1.0, 1.0),
+            skew        : (0.0, 0.0),
+        }
+    }
+}
+
+/// A single frame of a sprite.
+#[derive(Copy, Clone)]
+struct SpriteFrame {
+    atlas       : AtlasFrame,
+    /// Position of the (possibly trimmed) frame within the sprite's bounds.
+    offset      : Point,
+    /// Dimensions of the (possibly trimmed) frame.
+    dim         : Point,
+    pivot       : Option<(f32, f32)>,
+    duration    : Option<f32>,
+}
+
+/// Frames of a sprite, shared by its clones. Releases their atlas space when dropped.
+struct FrameSet {
+    frames      : Vec<SpriteFrame>,
+    context     : RenderContext,
+}
+
+impl Drop for FrameSet {
+    fn drop(&mut self) {
+        let atlas_frames: Vec<_> = self.frames.iter().map(|frame| frame.atlas).collect();
+        rendercontext::lock_poisoned(&self.context).release_frames(&atlas_frames);
+    }
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -39,57 +131,260 @@ impl<'a> Sprite {
     /// Creates a new sprite texture
     ///
     /// The given filename is epected to end on _<width>x<height>x<frames>.<extension>, e.g. asteroid_64x64x24.png.
+    /// If a metadata file with the same name and a .json extension exists (e.g. ship.json for ship.png), the
+    /// frame layout is read from the metadata file instead.
     pub fn from_file(context: &RenderContext, file: &str) -> Sprite {
+        Self::try_from_file(context, file).unwrap()
+    }
 
-        let (bucket_id, texture_size, frame_width, frame_height, raw_frames) = load_spritesheet(file);
-        let num_frames = raw_frames.len() as u32;
-        let texture_id = rendercontext::lock(context).store_frames(bucket_id, raw_frames);
-
-        Sprite {
-            width       : frame_width as f32,
-            height      : frame_height as f32,
-            num_frames  : num_frames,
-            anchor      : (0.5, 0.5),
-            bucket_id   : bucket_id,
-            texture_id  : texture_id,
-            u_max       : (frame_width as f32 / texture_size as f32),
-            v_max       : (frame_height as f32 / texture_size as f32),
-            context     : context.clone()
+    /// Creates a new sprite texture. Returns an error instead of panicking when the file cannot
+    /// be loaded. See [`Sprite::from_file()`](#method.from_file).
+    pub fn try_from_file(context: &RenderContext, file: &str) -> Result<Sprite, Error> {
+        let metadata_path = Path::new(file).with_extension("json");
+        if metadata_path.is_file() {
+            let metadata = load_metadata(&metadata_path)?;
+            create_sprite_from_metadata(context, image::open(file)?, metadata)
+        } else {
+            let (frame_width, frame_height, raw_frames) = load_spritesheet(file)?;
+            create_sprite(context, frame_width, frame_height, raw_frames)
         }
     }
-// !todo when sprite is dropped, texture would have to be removed (easy)
-// alters the texture ids of all following sprites (bah)
-//   a) create a lookup table in the context sprite_texture_id <-> texture_array_texture_id
-//  or b) keep a list of sprite instances in the context and mutate those (sounds sucky)
 
+    /// Creates a new sprite texture from a sprite sheet metadata file.
+    ///
+    /// The sprite sheet image is loaded from the location given in the metadata, relative to the
+    /// metadata file. If the metadata does not name an image, a png file with the same name as
+    /// the metadata file is used.
+    pub fn from_metadata(context: &RenderContext, file: &str) -> Sprite {
+        Self::try_from_metadata(context, file).unwrap()
+    }
+
+    /// Creates a new sprite texture from a sprite sheet metadata file. Returns an error instead
+    /// of panicking. See [`Sprite::from_metadata()`](#method.from_metadata).
+    pub fn try_from_metadata(context: &RenderContext, file: &str) -> Result<Sprite, Error> {
+        let path = Path::new(file);
+        let metadata = load_metadata(path)?;
+        let image_path = match metadata.image {
+            Some(ref image) => path.with_file_name(image),
+            None => path.with_extension("png"),
+        };
+        create_sprite_from_metadata(context, image::open(&image_path)?, metadata)
+    }
+
+    /// Creates a new sprite texture from an image and the contents of a sprite sheet metadata
+    /// file. The image name given in the metadata is ignored.
+    pub fn from_image_with_metadata(context: &RenderContext, image: image::DynamicImage, metadata: &str) -> Sprite {
+        Self::try_from_image_with_metadata(context, image, metadata).unwrap()
+    }
+
+    /// Creates a new sprite texture from an image and the contents of a sprite sheet metadata
+    /// file. Returns an error instead of panicking.
+    /// See [`Sprite::from_image_with_metadata()`](#method.from_image_with_metadata).
+    pub fn try_from_image_with_metadata(context: &RenderContext, image: image::DynamicImage, metadata: &str) -> Result<Sprite, Error> {
+        create_sprite_from_metadata(context, image, metadata::parse(metadata)?)
+    }
+
+    /// Creates a new sprite texture from an encoded image (e.g. the contents of a png file).
+    ///
+    /// The frames are expected to be arranged either in a single row or a single column.
+    pub fn from_bytes(context: &RenderContext, bytes: &[u8], frame_width: u32, frame_height: u32, frame_count: u32) -> Sprite {
+        Self::try_from_bytes(context, bytes, frame_width, frame_height, frame_count).unwrap()
+    }
+
+    /// Creates a new sprite texture from an encoded image. Returns an error instead of
+    /// panicking. See [`Sprite::from_bytes()`](#method.from_bytes).
+    pub fn try_from_bytes(context: &RenderContext, bytes: &[u8], frame_width: u32, frame_height: u32, frame_count: u32) -> Result<Sprite, Error> {
+        let image = image::load_from_memory(bytes)?;
+        Self::try_from_image(context, image, frame_width, frame_height, frame_count)
+    }
+
+    /// Creates a new sprite texture from an image.
+    ///
+    /// The frames are expected to be arranged either in a single row or a single column.
+    pub fn from_image(context: &RenderContext, image: image::DynamicImage, frame_width: u32, frame_height: u32, frame_count: u32) -> Sprite {
+        Self::try_from_image(context, image, frame_width, frame_height, frame_count).unwrap()
+    }
+
+    /// Creates a new sprite texture from an image. Returns an error instead of panicking.
+    /// See [`Sprite::from_image()`](#method.from_image).
+    pub fn try_from_image(context: &RenderContext, image: image::DynamicImage, frame_width: u32, frame_height: u32, frame_count: u32) -> Result<Sprite, Error> {
+        let frame_parameters = layout_parameters(image.dimensions(), frame_width, frame_height, frame_count)?;
+        let raw_frames = load_frames(image, &frame_parameters);
+        create_sprite(context, frame_width, frame_height, raw_frames)
+    }
+
+    /// Creates a new sprite texture from raw RGBA data.
+    ///
+    /// The data is expected to contain the given number of frames, each frame consisting of
+    /// width * height pixels with 4 bytes per pixel.
+    pub fn from_rgba(context: &RenderContext, width: u32, height: u32, frames: u32, data: &[u8]) -> Sprite {
+        Self::try_from_rgba(context, width, height, frames, data).unwrap()
+    }
+
+    /// Creates a new sprite texture from raw RGBA data. Returns an error instead of panicking.
+    /// See [`Sprite::from_rgba()`](#method.from_rgba).
+    pub fn try_from_rgba(context: &RenderContext, width: u32, height: u32, frames: u32, data: &[u8]) -> Result<Sprite, Error> {
+
+        let frame_size = (width as usize).checked_mul(height as usize).and_then(|size| size.checked_mul(4)).unwrap_or(0);
+
+        if frame_size == 0 || frames == 0 || frame_size.checked_mul(frames as usize) != Some(data.len()) {
+            return Err(Error::SheetLayoutError(format!("{} bytes of data do not match {} frames of {}x{} pixels", data.len(), frames, width, height)));
+        }
+
+        let raw_frames = data.chunks(frame_size).map(|frame| RenderContextTexture {
+            data: frame.to_vec(),
+            width: width,
+            height: height,
+        }).collect();
+
+        create_sprite(context, width, height, raw_frames)
+    }
 
     /// Draws a sprite onto the given layer.
     pub fn draw(self: &Self, layer: &Layer, frame_id: u32, x: f32, y: f32, color: Color) -> &Self {
+        self.draw_transformed(layer, frame_id, x, y, color, 0.0, 1.0, 1.0)
+    }
+
+    /// Draws a sprite onto the given layer and applies given color, rotation and scaling.
+    pub fn draw_transformed(self: &Self, layer: &Layer, frame_id: u32, x: f32, y: f32, color: Color, rotation: f32, scale_x: f32, scale_y: f32) -> &Self {
+
+        let frame = self.frame(frame_id);
+        let (anchor_x, anchor_y) = frame.pivot.unwrap_or(self.anchor);
 
-        let bucket_id = self.bucket_id;
-        let texture_id = self.texture_id(frame_id);
-        let uv = Rect::new(0.0, 0.0, self.u_max, self.v_max);
-        let anchor = Point::new(self.anchor.0, self.anchor.1);
+        // the anchor refers to the untrimmed frame, make it relative to the trimmed one
+        let anchor = Point::new(
+            (anchor_x * self.width - frame.offset.x) / frame.dim.x,
+            (anchor_y * self.height - frame.offset.y) / frame.dim.y
+        );
         let pos = Point::new(x, y);
-        let dim = Point::new(self.width, self.height);
-        let scale = Point::new(1.0, 1.0);
+        let scale = Point::new(scale_x, scale_y);
 
-        layer::add_rect(layer, bucket_id, texture_id, uv, pos, anchor, dim, color, 0.0, scale);
+        layer::add_rect(layer, rendercontext::ATLAS_BUCKET, frame.atlas.page, frame.atlas.uv, pos, anchor, frame.dim, color, rotation, scale);
         self
     }
 
-    /// Draws a sprite onto the given layer and applies given color, rotation and scaling.
-    pub fn draw_transformed(self: &Self, layer: &Layer, frame_id: u32, x: f32, y: f32, color: Color, rotation: f32, scale_x: f32, scale_y: f32) -> &Self {
+    /// Draws a sprite onto the given layer using given options, see [`DrawParams`](struct.DrawParams.html).
+    pub fn draw_with(self: &Self, layer: &Layer, frame_id: u32, x: f32, y: f32, params: &DrawParams) -> &Self {
+
+        let frame = self.frame(frame_id);
+        let (source_x, source_y, source_width, source_height) = params.source.unwrap_or((0.0, 0.0, self.width, self.height));
+        let (anchor_x, anchor_y) = params.anchor.or(frame.pivot).unwrap_or(self.anchor);
+
+        // visible part of the source rectangle, clipped to the trimmed frame
+
+        let x0 = source_x.max(frame.offset.x);
+        let x1 = (source_x + source_width).min(frame.offset.x + frame.dim.x);
+        let y0 = source_y.max(frame.offset.y);
+        let y1 = (source_y + source_height).min(frame.offset.y + frame.dim.y);
+
+        if x1 <= x0 || y1 <= y0 {
+            return self;
+        }
+
+        let (uv0, uv1) = (frame.atlas.uv.0, frame.atlas.uv.1);
+        let uv_scale = Point::new((uv1.x - uv0.x) / frame.dim.x, (uv1.y - uv0.y) / frame.dim.y);
+        let origin = Point::new(source_x + anchor_x * source_width, source_y + anchor_y * source_height);
+        let (skew_x, skew_y) = (params.skew.0.tan(), params.skew.1.tan());
+
+        let corner = |x: f32, y: f32| {
+
+            // offset from the anchor, flipped, scaled and skewed
+
+            let mut offset_x = (x - origin.x) * if params.flip_x { -params.scale.0 } else { params.scale.0 };
+            let mut offset_y = (y - origin.y) * if params.flip_y { -params.scale.1 } else { params.scale.1 };
+            let skewed_x = offset_x + offset_y * skew_x;
+            offset_y += offset_x * skew_y;
+            offset_x = skewed_x;
+
+            // interpolate corner colors by the position within the source rectangle as drawn
+
+            let mut u = if source_width > 0.0 { (x - source_x) / source_width } else { 0.0 };
+            let mut v = if source_height > 0.0 { (y - source_y) / source_height } else { 0.0 };
+            if params.flip_x { u = 1.0 - u; }
+            if params.flip_y { v = 1.0 - v; }
+            let colors = &params.colors;
+            let color = mix(mix(colors[0], colors[1], u), mix(colors[2], colors[3], u), v);
+
+            let uv = Point::new(uv0.x + (x - frame.offset.x) * uv_scale.x, uv0.y + (y - frame.offset.y) * uv_scale.y);
+            (Point::new(offset_x, offset_y), uv, color)
+        };
+
+        let corners = [ corner(x0, y0), corner(x1, y0), corner(x0, y1), corner(x1, y1) ];
+        layer::add_corners(layer, rendercontext::ATLAS_BUCKET, frame.atlas.page, Point::new(x, y), params.rotation, corners);
+        self
+    }
+
+    /// Draws a sprite stretched to width x height pixels onto the given layer, keeping the size
+    /// of the borders defined by its [`insets`](#structfield.insets). The anchor is relative to
+    /// the given size. If the size is smaller than the borders, they are scaled down to fit.
+    pub fn draw_nine_slice(self: &Self, layer: &Layer, frame_id: u32, x: f32, y: f32, width: f32, height: f32, color: Color) -> &Self {
+        self.draw_nine_slice_transformed(layer, frame_id, x, y, width, height, color, 0.0)
+    }
+
+    /// Draws a sprite stretched to width x height pixels onto the given layer and applies given
+    /// color and rotation. See [`Sprite::draw_nine_slice()`](#method.draw_nine_slice).
+    pub fn draw_nine_slice_transformed(self: &Self, layer: &Layer, frame_id: u32, x: f32, y: f32, width: f32, height: f32, color: Color, rotation: f32) -> &Self {
+
+        let frame = self.frame(frame_id);
+        let (anchor_x, anchor_y) = frame.pivot.unwrap_or(self.anchor);
+        let (left, top, right, bottom) = self.insets.unwrap_or((0.0, 0.0, 0.0, 0.0));
+
+        // slice boundaries within the untrimmed frame and within the target rectangle
 
-        let bucket_id = self.bucket_id;
-        let texture_id = self.texture_id(frame_id);
-        let uv = Rect::new(0.0, 0.0, self.u_max, self.v_max);
-        let anchor = Point::new(self.anchor.0, self.anchor.1);
+        let fit = |start: f32, end: f32, size: f32| if start + end > size && start + end > 0.0 { size / (start + end) } else { 1.0 };
+        let (fit_x, fit_y) = (fit(left, right, width), fit(top, bottom, height));
+        let source_x = [ 0.0, left, self.width - right, self.width ];
+        let source_y = [ 0.0, top, self.height - bottom, self.height ];
+        let target_x = [ 0.0, left * fit_x, width - right * fit_x, width ];
+        let target_y = [ 0.0, top * fit_y, height - bottom * fit_y, height ];
+
+        // maps a position within a slice of the untrimmed frame to the target rectangle
+        let map = |value: f32, slice: usize, source: &[f32; 4], target: &[f32; 4]| {
+            let size = source[slice + 1] - source[slice];
+            if size > 0.0 { target[slice] + (value - source[slice]) * (target[slice + 1] - target[slice]) / size } else { target[slice] }
+        };
+
+        let (uv0, uv1) = (frame.atlas.uv.0, frame.atlas.uv.1);
+        let uv_scale = Point::new((uv1.x - uv0.x) / frame.dim.x, (uv1.y - uv0.y) / frame.dim.y);
+        let origin = Point::new(anchor_x * width, anchor_y * height);
         let pos = Point::new(x, y);
-        let dim = Point::new(self.width, self.height);
-        let scale = Point::new(scale_x, scale_y);
 
-        layer::add_rect(layer, bucket_id, texture_id, uv, pos, anchor, dim, color, rotation, scale);
+        for row in 0..3 {
+            for column in 0..3 {
+
+                // clip the slice to the trimmed frame
+
+                let x0 = source_x[column].max(frame.offset.x);
+                let x1 = source_x[column + 1].min(frame.offset.x + frame.dim.x);
+                let y0 = source_y[row].max(frame.offset.y);
+                let y1 = source_y[row + 1].min(frame.offset.y + frame.dim.y);
+
+                if x1 <= x0 || y1 <= y0 {
+                    continue;
+                }
+
+                let (target_left, target_right) = (map(x0, column, &source_x, &target_x), map(x1, column, &source_x, &target_x));
+                let (target_top, target_bottom) = (map(y0, row, &source_y, &target_y), map(y1, row, &source_y, &target_y));
+                let dim = Point::new(target_right - target_left, target_bottom - target_top);
+
+                if dim.x <= 0.0 || dim.y <= 0.0 {
+                    continue;
+                }
+
+                let uv = Rect::new(
+                    uv0.x + (x0 - frame.offset.x) * uv_scale.x,
+                    uv0.y + (y0 - frame.offset.y) * uv_scale.y,
+                    uv0.x + (x1 - frame.offset.x) * uv_scale.x,
+                    uv0.y + (y1 - frame.offset.y) * uv_scale.y
+                );
+
+                // anchor all slices at the same point so that they rotate as one
+                let anchor = Point::new((origin.x - target_left) / dim.x, (origin.y - target_top) / dim.y);
+
+                layer::add_rect(layer, rendercontext::ATLAS_BUCKET, frame.atlas.page, uv, pos, anchor, dim, color, rotation, Point::new(1.0, 1.0));
+            }
+        }
+
         self
     }
 
@@ -108,86 +403,213 @@ impl<'a> Sprite {
         self.num_frames
     }
 
-    /// Returns the texture id for given frame
-    fn texture_id(self: &Self, frame_id: u32) -> u32 {
-        self.texture_id + (frame_id % self.num_frames)
+    /// Returns the duration of given frame in seconds, if defined by the sprite sheet metadata.
+    pub fn frame_duration(self: &Self, frame_id: u32) -> Option<f32> {
+        self.frame(frame_id).duration
+    }
+
+    /// Returns the first and last frame of the named frame range defined by the sprite sheet
+    /// metadata. The first frame is larger than the last frame for ranges meant to be played
+    /// in reverse.
+    pub fn frame_range(self: &Self, name: &str) -> Option<(u32, u32)> {
+        self.ranges.get(name).cloned()
+    }
+
+    /// Returns the names of all frame ranges defined by the sprite sheet metadata.
+    pub fn frame_range_names(self: &Self) -> Vec<String> {
+        self.ranges.keys().cloned().collect()
+    }
+
+    /// Returns the given frame
+    fn frame(self: &Self, frame_id: u32) -> SpriteFrame {
+        self.frames.frames[(frame_id % self.num_frames) as usize]
     }
 }
 
-/// loads a spritesheet and returns a vector of frames
-pub fn load_spritesheet<'b>(file: &str) -> (u32, u32, u32, u32, Vec<RenderContextTexture>) {
+/// Returns the atlas location of given frame.
+pub fn atlas_frame(sprite: &Sprite, frame_id: u32) -> AtlasFrame {
+    sprite.frame(frame_id).atlas
+}
+
+/// linearly interpolates between two colors
+fn mix(a: Color, b: Color, amount: f32) -> Color {
+    Color(a.0 + (b.0 - a.0) * amount, a.1 + (b.1 - a.1) * amount, a.2 + (b.2 - a.2) * amount, a.3 + (b.3 - a.3) * amount)
+}
+
+/// loads a spritesheet and returns frame dimensions and a vector of frames
+pub fn load_spritesheet<'b>(file: &str) -> Result<(u32, u32, Vec<RenderContextTexture>), Error> {
 
     // load image file
 
     let path = Path::new(file);
-    let mut image = image::open(&path).unwrap();
-    let image_dimensions = image.dimensions();
+    let image = image::open(&path)?;
 
     // compute frame parameters
 
-    let frame_parameters = parse_parameters(image_dimensions, path);
-    let FrameParameters(frame_width, frame_height, frame_count, _) = frame_parameters;
-    let (bucket_id, pad_size) = renderer::bucket_info(frame_width, frame_height);
+    let frame_parameters = parse_parameters(image.dimensions(), path)?;
+    let FrameParameters(frame_width, frame_height, _, _) = frame_parameters;
+
+    Ok((frame_width, frame_height, load_frames(image, &frame_parameters)))
+}
+
+/// reads and parses a sprite sheet metadata file
+fn load_metadata(path: &Path) -> Result<SheetMetadata, Error> {
+    let mut json = String::new();
+    File::open(path)?.read_to_string(&mut json)?;
+    metadata::parse(&json)
+}
+
+/// creates a sprite from given frames
+fn create_sprite(context: &RenderContext, frame_width: u32, frame_height: u32, raw_frames: Vec<RenderContextTexture>) -> Result<Sprite, Error> {
+
+    let dim = Point::new(frame_width as f32, frame_height as f32);
+    let frames = raw_frames.iter().map(|_| SpriteFrame {
+        atlas       : AtlasFrame { page: 0, uv: Rect::new(0.0, 0.0, 0.0, 0.0) },
+        offset      : Point::new(0.0, 0.0),
+        dim         : dim,
+        pivot       : None,
+        duration    : None,
+    }).collect();
+
+    build_sprite(context, frame_width, frame_height, raw_frames, frames, HashMap::new())
+}
+
+/// creates a sprite from a sprite sheet image and its metadata
+fn create_sprite_from_metadata(context: &RenderContext, mut image: image::DynamicImage, metadata: SheetMetadata) -> Result<Sprite, Error> {
+
+    let (image_width, image_height) = image.dimensions();
+    let mut raw_frames = Vec::with_capacity(metadata.frames.len());
+    let mut frames = Vec::with_capacity(metadata.frames.len());
+    let mut width = 0;
+    let mut height = 0;
+
+    for frame in metadata.frames.iter() {
+
+        // rotated frames are stored rotated clockwise, their rect gives the unrotated size
+        let (x, y, frame_width, frame_height) = frame.rect;
+        let (sheet_width, sheet_height) = if frame.rotated { (frame_height, frame_width) } else { (frame_width, frame_height) };
+
+        let fits = x.checked_add(sheet_width).map_or(false, |right| right <= image_width) && y.checked_add(sheet_height).map_or(false, |bottom| bottom <= image_height);
+
+        if frame_width == 0 || frame_height == 0 || !fits {
+            return Err(Error::SheetLayoutError(format!("frame at {}x{} with {}x{} pixels does not fit image size {}x{}", x, y, sheet_width, sheet_height, image_width, image_height)));
+        }
+
+        let mut subimage = image.crop(x, y, sheet_width, sheet_height);
+
+        if frame.rotated {
+            subimage = subimage.rotate270();
+        }
+
+        raw_frames.push(RenderContextTexture {
+            data: subimage.to_rgba().into_raw(),
+            width: frame_width,
+            height: frame_height,
+        });
+
+        frames.push(SpriteFrame {
+            atlas       : AtlasFrame { page: 0, uv: Rect::new(0.0, 0.0, 0.0, 0.0) },
+            offset      : Point::new(frame.offset.0 as f32, frame.offset.1 as f32),
+            dim         : Point::new(frame_width as f32, frame_height as f32),
+            pivot       : frame.pivot,
+            duration    : frame.duration,
+        });
+
+        width = cmp::max(width, frame.source.0);
+        height = cmp::max(height, frame.source.1);
+    }
 
+    let mut sprite = build_sprite(context, width, height, raw_frames, frames, metadata.ranges)?;
+    sprite.insets = metadata.insets.map(|(left, top, right, bottom)| (left as f32, top as f32, right as f32, bottom as f32));
+    Ok(sprite)
+}
+
+/// stores given frames in the atlas and creates a sprite from them
+fn build_sprite(context: &RenderContext, width: u32, height: u32, raw_frames: Vec<RenderContextTexture>, mut frames: Vec<SpriteFrame>, ranges: HashMap<String, (u32, u32)>) -> Result<Sprite, Error> {
+
+    let num_frames = raw_frames.len() as u32;
+    let atlas_frames = rendercontext::lock(context).store_frames(raw_frames)?;
+
+    for (frame, atlas_frame) in frames.iter_mut().zip(atlas_frames) {
+        frame.atlas = atlas_frame;
+    }
+
+    Ok(Sprite {
+        width       : width as f32,
+        height      : height as f32,
+        num_frames  : num_frames,
+        anchor      : (0.5, 0.5),
+        insets      : None,
+        frames      : Arc::new(FrameSet { frames: frames, context: context.clone() }),
+        ranges      : Arc::new(ranges),
+    })
+}
+
+/// splits a spritesheet image into frames
+fn load_frames(mut image: image::DynamicImage, frame_parameters: &FrameParameters) -> Vec<RenderContextTexture> {
+
+    let image_dimensions = image.dimensions();
+    let FrameParameters(_, _, frame_count, _) = *frame_parameters;
     let mut raw_frames = Vec::new();
 
     for frame_id in 0..frame_count {
-        raw_frames.push(build_frame_texture(&mut image, image_dimensions, &frame_parameters, frame_id, pad_size));
+        raw_frames.push(build_frame_texture(&mut image, image_dimensions, frame_parameters, frame_id));
     }
 
-    (bucket_id, pad_size, frame_width, frame_height, raw_frames)
+    raw_frames
 }
 
 /// parses sprite-sheet filename for dimensions and frame count
-fn parse_parameters(dimensions: (u32, u32), path: &Path) -> FrameParameters {
+fn parse_parameters(dimensions: (u32, u32), path: &Path) -> Result<FrameParameters, Error> {
 
     lazy_static! { static ref MATCHER: Regex = Regex::new(r"_(\d+)x(\d+)x(\d+)\.").unwrap(); }
 
-    let filename = path.file_name().unwrap().to_str().unwrap();
+    let filename = path.file_name().and_then(|filename| filename.to_str()).unwrap_or("");
     let captures = MATCHER.captures(filename);
 
     match captures {
         Some(captures) => {
-            let frame_width = captures.at(1).unwrap().parse::<u32>().unwrap();
-            let frame_height = captures.at(2).unwrap().parse::<u32>().unwrap();
-            let frame_count = captures.at(3).unwrap().parse::<u32>().unwrap();
-            let frame_layout = if frame_height == dimensions.1 { SpriteLayout::HORIZONTAL } else { SpriteLayout::VERTICAL };
-            assert!(frame_layout == SpriteLayout::VERTICAL || frame_width * frame_count == dimensions.0);
-            assert!(frame_layout == SpriteLayout::HORIZONTAL || frame_height * frame_count == dimensions.1);
-            FrameParameters(frame_width, frame_height, frame_count, frame_layout)
+            let parse = |index| captures.at(index).unwrap().parse::<u32>().map_err(|_| Error::SheetLayoutError(format!("invalid frame parameters in filename {}", filename)));
+            let frame_width = parse(1)?;
+            let frame_height = parse(2)?;
+            let frame_count = parse(3)?;
+            layout_parameters(dimensions, frame_width, frame_height, frame_count)
         }
-        None => FrameParameters(dimensions.0, dimensions.1, 1, SpriteLayout::HORIZONTAL)
+        None => Ok(FrameParameters(dimensions.0, dimensions.1, 1, SpriteLayout::HORIZONTAL))
     }
 }
 
-/// constructs a RawFrame for a single frame of a spritesheet
-///
-/// if neccessary, pads the image up to the next power of two
-fn build_frame_texture<'b>(image: &mut image::DynamicImage, image_dimensions: (u32, u32), frame_parameters: &FrameParameters, frame_id: u32, pad_size: u32) -> RenderContextTexture {
+/// determines spritesheet layout from given image and frame dimensions
+fn layout_parameters(dimensions: (u32, u32), frame_width: u32, frame_height: u32, frame_count: u32) -> Result<FrameParameters, Error> {
 
-    let FrameParameters(frame_width, frame_height, _, _) = *frame_parameters;
-    let (x, y) = get_frame_coordinates(image_dimensions, frame_parameters, frame_id);
-    let subimage = image.crop(x, y, frame_width, frame_height);
+    let frame_layout = if frame_height == dimensions.1 { SpriteLayout::HORIZONTAL } else { SpriteLayout::VERTICAL };
 
-    if frame_width != pad_size || frame_height != pad_size {
+    // products that overflow can't match the image size
 
-        // pad image if it doesn't match an available texture array size
-        let mut dest = image::DynamicImage::new_rgba8(pad_size, pad_size);
-        dest.copy_from(&subimage, 0, 0);
-        RenderContextTexture {
-            data: dest.to_rgba().into_raw(),
-            width: pad_size,
-            height: pad_size,
-        }
+    let valid = frame_width > 0 && frame_height > 0 && frame_count > 0 && if frame_layout == SpriteLayout::HORIZONTAL {
+        frame_width.checked_mul(frame_count) == Some(dimensions.0)
+    } else {
+        frame_width <= dimensions.0 && frame_height.checked_mul(frame_count) == Some(dimensions.1)
+    };
 
+    if valid {
+        Ok(FrameParameters(frame_width, frame_height, frame_count, frame_layout))
     } else {
+        Err(Error::SheetLayoutError(format!("{} frames of {}x{} pixels do not match image size {}x{}", frame_count, frame_width, frame_height, dimensions.0, dimensions.1)))
+    }
+}
 
-        // perfect fit
-        RenderContextTexture {
-            data: subimage.to_rgba().into_raw(),
-            width: frame_width,
-            height: frame_height,
-        }
+/// constructs a RawFrame for a single frame of a spritesheet
+fn build_frame_texture<'b>(image: &mut image::DynamicImage, image_dimensions: (u32, u32), frame_parameters: &FrameParameters, frame_id: u32) -> RenderContextTexture {
+
+    let FrameParameters(frame_width, frame_height, _, _) = *frame_parameters;
+    let (x, y) = get_frame_coordinates(image_dimensions, frame_parameters, frame_id);
+    let subimage = image.crop(x, y, frame_width, frame_height);
+
+    RenderContextTexture {
+        data: subimage.to_rgba().into_raw(),
+        width: frame_width,
+        height: frame_height,
     }
 }
 
@@ -0,0 +1,138 @@
+use prelude::*;
+use glium;
+use glium::Surface;
+use image;
+use core::{display, layer, Layer, Point, Rect, rendercontext, RenderContext, Color, Error};
+
+static TEXTURE_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// A texture that layers can be rendered to and that can itself be drawn onto a
+/// [`Layer`](struct.Layer.html).
+///
+/// Use [`Renderer::render_to()`](struct.Renderer.html#method.render_to) to redirect layer
+/// drawing into the texture. Afterwards, the texture can be drawn onto other layers like a
+/// single frame sprite, e.g. to build minimaps or interface panels.
+pub struct Texture {
+    texture_id  : usize,
+    width       : u32,
+    height      : u32,
+    context     : RenderContext,
+}
+
+impl Texture {
+
+    /// Creates a new, transparent texture with given dimensions.
+    pub fn new(context: &RenderContext, width: u32, height: u32) -> Texture {
+        Self::try_new(context, width, height).unwrap()
+    }
+
+    /// Creates a new, transparent texture with given dimensions. Returns an error if the texture
+    /// could not be created.
+    pub fn try_new(context: &RenderContext, width: u32, height: u32) -> Result<Texture, Error> {
+
+        let texture_id = TEXTURE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mut context_data = rendercontext::lock(context);
+
+        let texture = glium::texture::Texture2d::empty_with_format(
+            display::handle(&context_data.display),
+            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+            glium::texture::MipmapsOption::NoMipmap,
+            width,
+            height
+        ).map_err(|error| Error::GlError(format!("{:?}", error)))?;
+        texture.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+        context_data.textures.insert(texture_id, texture);
+
+        Ok(Texture {
+            texture_id  : texture_id,
+            width       : width,
+            height      : height,
+            context     : context.clone(),
+        })
+    }
+
+    /// Creates a new texture from an image, e.g. a color lookup table for
+    /// [`effects::lut()`](effects/fn.lut.html).
+    pub fn from_image(context: &RenderContext, image: &image::DynamicImage) -> Texture {
+        Self::try_from_image(context, image).unwrap()
+    }
+
+    /// Creates a new texture from an image. Returns an error if the texture could not be created.
+    pub fn try_from_image(context: &RenderContext, image: &image::DynamicImage) -> Result<Texture, Error> {
+
+        let texture_id = TEXTURE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mut context_data = rendercontext::lock(context);
+
+        let image = image.to_rgba();
+        let (width, height) = image.dimensions();
+        let raw = glium::texture::RawImage2d::from_raw_rgba_reversed(image.into_raw(), (width, height));
+
+        let texture = glium::texture::Texture2d::with_format(
+            display::handle(&context_data.display),
+            raw,
+            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+            glium::texture::MipmapsOption::NoMipmap
+        ).map_err(|error| Error::GlError(format!("{:?}", error)))?;
+        context_data.textures.insert(texture_id, texture);
+
+        Ok(Texture {
+            texture_id  : texture_id,
+            width       : width,
+            height      : height,
+            context     : context.clone(),
+        })
+    }
+
+    /// Clears the texture with given color.
+    pub fn clear(self: &Self, color: Color) -> &Self {
+        let context = rendercontext::lock(&self.context);
+        let (r, g, b, a) = color.as_tuple();
+        context.textures[&self.texture_id].as_surface().clear_color(r, g, b, a);
+        self
+    }
+
+    /// Draws the texture onto the given layer.
+    pub fn draw(self: &Self, layer: &Layer, x: f32, y: f32, color: Color) -> &Self {
+        self.draw_transformed(layer, x, y, color, 0.0, 1.0, 1.0)
+    }
+
+    /// Draws the texture onto the given layer and applies given color, rotation and scaling.
+    pub fn draw_transformed(self: &Self, layer: &Layer, x: f32, y: f32, color: Color, rotation: f32, scale_x: f32, scale_y: f32) -> &Self {
+
+        // textures are stored bottom-up, flip vertically
+        let uv = Rect::new(0.0, 1.0, 1.0, 0.0);
+        let anchor = Point::new(0.5, 0.5);
+        let pos = Point::new(x, y);
+        let dim = Point::new(self.width as f32, self.height as f32);
+        let scale = Point::new(scale_x, scale_y);
+
+        layer::add_rect(layer, rendercontext::TEXTURE_BUCKET, self.texture_id as u32, uv, pos, anchor, dim, color, rotation, scale);
+        self
+    }
+
+    /// Returns the width of the texture.
+    pub fn width(self: &Self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the texture.
+    pub fn height(self: &Self) -> u32 {
+        self.height
+    }
+
+    /// Returns the width and height of the texture.
+    pub fn dimensions(self: &Self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl Drop for Texture {
+    fn drop(self: &mut Self) {
+        rendercontext::lock(&self.context).textures.remove(&self.texture_id);
+    }
+}
+
+/// Returns the id the texture is registered under in the render context.
+pub fn id(texture: &Texture) -> usize {
+    texture.texture_id
+}
@@ -0,0 +1,324 @@
+use prelude::*;
+use serde_json;
+use serde_json::Value;
+use xml::reader::{EventReader, XmlEvent};
+use xml::attribute::OwnedAttribute;
+use flate2::read::{ZlibDecoder, GzDecoder};
+use rustc_serialize::base64::FromBase64;
+use core::Error;
+use std::str::FromStr;
+
+/// A tileset as described by a Tiled map or tileset file. All values are in pixels.
+pub struct TilesetData {
+    /// Global id of the first tile of the tileset.
+    pub first_gid   : u32,
+    /// External tileset file, relative to the map file. The remaining values are only set for
+    /// embedded tilesets.
+    pub source      : Option<String>,
+    /// Tileset image, relative to the file defining the tileset.
+    pub image       : Option<String>,
+    pub tile_width  : u32,
+    pub tile_height : u32,
+    pub margin      : u32,
+    pub spacing     : u32,
+    pub columns     : Option<u32>,
+    pub tile_count  : Option<u32>,
+}
+
+/// A tile layer as described by a Tiled map file.
+pub struct LayerData {
+    pub name        : String,
+    pub visible     : bool,
+    /// Global tile ids including flip flags, row by row. 0 is an empty tile.
+    pub tiles       : Vec<u32>,
+}
+
+/// An orthogonal Tiled map.
+pub struct MapData {
+    /// Map dimensions in tiles.
+    pub width       : u32,
+    pub height      : u32,
+    pub tile_width  : u32,
+    pub tile_height : u32,
+    pub tilesets    : Vec<TilesetData>,
+    pub layers      : Vec<LayerData>,
+}
+
+/// Parses a map in Tiled's XML format (.tmx).
+pub fn parse_tmx(xml: &str) -> Result<MapData, Error> {
+
+    let (map, tilesets, layers) = read_xml(xml)?;
+    let map = map.ok_or_else(|| error("missing map element"))?;
+    check_map(map.orientation.as_ref().map(|orientation| orientation.as_str()), map.infinite)?;
+
+    Ok(MapData {
+        width       : map.width,
+        height      : map.height,
+        tile_width  : map.tile_width,
+        tile_height : map.tile_height,
+        tilesets    : tilesets,
+        layers      : layers,
+    })
+}
+
+/// Parses an external tileset in Tiled's XML format (.tsx).
+pub fn parse_tsx(xml: &str) -> Result<TilesetData, Error> {
+    let (_, mut tilesets, _) = read_xml(xml)?;
+    if tilesets.is_empty() { Err(error("missing tileset element")) } else { Ok(tilesets.remove(0)) }
+}
+
+/// Parses a map in Tiled's JSON format.
+pub fn parse_json(json: &str) -> Result<MapData, Error> {
+
+    let root: Value = serde_json::from_str(json).map_err(|error| Error::TilemapError(format!("{}", error)))?;
+    check_map(root.find("orientation").and_then(Value::as_str), root.find("infinite").and_then(Value::as_bool).unwrap_or(false))?;
+
+    let tilesets = match root.find("tilesets").and_then(Value::as_array) {
+        Some(tilesets) => tilesets.iter().map(json_tileset).collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+
+    let mut layers = Vec::new();
+    if let Some(json_layers) = root.find("layers").and_then(Value::as_array) {
+        json_tile_layers(json_layers, &mut layers)?;
+    }
+
+    Ok(MapData {
+        width       : json_number(&root, "width")?,
+        height      : json_number(&root, "height")?,
+        tile_width  : json_number(&root, "tilewidth")?,
+        tile_height : json_number(&root, "tileheight")?,
+        tilesets    : tilesets,
+        layers      : layers,
+    })
+}
+
+/// Parses an external tileset in Tiled's JSON format.
+pub fn parse_tileset_json(json: &str) -> Result<TilesetData, Error> {
+    let root: Value = serde_json::from_str(json).map_err(|error| Error::TilemapError(format!("{}", error)))?;
+    json_tileset(&root)
+}
+
+/// Attributes of the map element.
+struct MapHeader {
+    orientation : Option<String>,
+    infinite    : bool,
+    width       : u32,
+    height      : u32,
+    tile_width  : u32,
+    tile_height : u32,
+}
+
+/// Reads map, tileset and tile layer elements from a .tmx or .tsx file. Tile layers nested in
+/// groups are flattened.
+fn read_xml(xml: &str) -> Result<(Option<MapHeader>, Vec<TilesetData>, Vec<LayerData>), Error> {
+
+    let mut map = None;
+    let mut tilesets = Vec::new();
+    let mut layers = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+
+    // state of the tile layer currently being read
+    let mut layer: Option<LayerData> = None;
+    let mut encoding: Option<String> = None;
+    let mut compression: Option<String> = None;
+    let mut data = String::new();
+
+    for event in EventReader::new(xml.as_bytes()) {
+        match event.map_err(|error| Error::TilemapError(format!("{}", error)))? {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let parent = stack.last().map(|parent| parent.as_str());
+                match (name.local_name.as_str(), parent) {
+                    ("map", None) => {
+                        map = Some(MapHeader {
+                            orientation : attribute(&attributes, "orientation"),
+                            infinite    : attribute(&attributes, "infinite").map_or(false, |infinite: u32| infinite != 0),
+                            width       : required(&attributes, "width")?,
+                            height      : required(&attributes, "height")?,
+                            tile_width  : required(&attributes, "tilewidth")?,
+                            tile_height : required(&attributes, "tileheight")?,
+                        });
+                    },
+                    ("tileset", None) | ("tileset", Some("map")) => {
+                        tilesets.push(TilesetData {
+                            first_gid   : attribute(&attributes, "firstgid").unwrap_or(1),
+                            source      : attribute(&attributes, "source"),
+                            image       : None,
+                            tile_width  : attribute(&attributes, "tilewidth").unwrap_or(0),
+                            tile_height : attribute(&attributes, "tileheight").unwrap_or(0),
+                            margin      : attribute(&attributes, "margin").unwrap_or(0),
+                            spacing     : attribute(&attributes, "spacing").unwrap_or(0),
+                            columns     : attribute(&attributes, "columns"),
+                            tile_count  : attribute(&attributes, "tilecount"),
+                        });
+                    },
+                    ("image", Some("tileset")) => {
+                        if let Some(tileset) = tilesets.last_mut() {
+                            tileset.image = attribute(&attributes, "source");
+                        }
+                    },
+                    ("image", Some("tile")) => {
+                        return Err(error("image collection tilesets are not supported"));
+                    },
+                    ("layer", _) => {
+                        layer = Some(LayerData {
+                            name        : attribute(&attributes, "name").unwrap_or_else(String::new),
+                            visible     : attribute(&attributes, "visible").map_or(true, |visible: u32| visible != 0),
+                            tiles       : Vec::new(),
+                        });
+                    },
+                    ("data", Some("layer")) => {
+                        encoding = attribute(&attributes, "encoding");
+                        compression = attribute(&attributes, "compression");
+                        data.clear();
+                    },
+                    ("tile", Some("data")) => {
+                        if let Some(ref mut layer) = layer {
+                            layer.tiles.push(attribute(&attributes, "gid").unwrap_or(0));
+                        }
+                    },
+                    ("chunk", Some("data")) => {
+                        return Err(error("infinite maps are not supported"));
+                    },
+                    _ => { },
+                }
+                stack.push(name.local_name);
+            },
+            XmlEvent::Characters(text) => {
+                if stack.last().map_or(false, |element| element == "data") {
+                    data.push_str(&text);
+                }
+            },
+            XmlEvent::EndElement { name } => {
+                if name.local_name == "layer" {
+                    if let Some(mut layer) = layer.take() {
+                        if let Some(ref encoding) = encoding {
+                            layer.tiles = decode(&data, encoding, compression.as_ref().map(|compression| compression.as_str()))?;
+                        }
+                        layers.push(layer);
+                    }
+                    encoding = None;
+                    compression = None;
+                }
+                stack.pop();
+            },
+            _ => { },
+        }
+    }
+
+    Ok((map, tilesets, layers))
+}
+
+/// Returns an error for map types that are not supported.
+fn check_map(orientation: Option<&str>, infinite: bool) -> Result<(), Error> {
+    match orientation {
+        Some("orthogonal") | None => { },
+        Some(orientation) => return Err(Error::TilemapError(format!("{} maps are not supported", orientation))),
+    }
+    if infinite {
+        Err(error("infinite maps are not supported"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Reads a tileset from its JSON representation.
+fn json_tileset(value: &Value) -> Result<TilesetData, Error> {
+    let string = |key: &str| value.find(key).and_then(Value::as_str).map(|string| string.to_string());
+    let number = |key: &str| value.find(key).and_then(Value::as_u64).map(|number| number as u32);
+    if value.find("tiles").and_then(Value::as_array).map_or(false, |tiles| tiles.iter().any(|tile| tile.find("image").is_some())) {
+        return Err(error("image collection tilesets are not supported"));
+    }
+    Ok(TilesetData {
+        first_gid   : number("firstgid").unwrap_or(1),
+        source      : string("source"),
+        image       : string("image"),
+        tile_width  : number("tilewidth").unwrap_or(0),
+        tile_height : number("tileheight").unwrap_or(0),
+        margin      : number("margin").unwrap_or(0),
+        spacing     : number("spacing").unwrap_or(0),
+        columns     : number("columns"),
+        tile_count  : number("tilecount"),
+    })
+}
+
+/// Appends all tile layers, including those nested in groups.
+fn json_tile_layers(values: &[Value], layers: &mut Vec<LayerData>) -> Result<(), Error> {
+    for value in values {
+        match value.find("type").and_then(Value::as_str) {
+            Some("tilelayer") => {
+                if value.find("chunks").is_some() {
+                    return Err(error("infinite maps are not supported"));
+                }
+                let tiles = match value.find("data") {
+                    Some(&Value::Array(ref data)) => data.iter().map(|gid| gid.as_u64().map(|gid| gid as u32).ok_or_else(|| error("invalid tile id"))).collect::<Result<Vec<_>, _>>()?,
+                    Some(&Value::String(ref data)) => {
+                        let encoding = value.find("encoding").and_then(Value::as_str).unwrap_or("base64");
+                        decode(data, encoding, value.find("compression").and_then(Value::as_str))?
+                    },
+                    _ => return Err(error("missing layer data")),
+                };
+                layers.push(LayerData {
+                    name        : value.find("name").and_then(Value::as_str).unwrap_or("").to_string(),
+                    visible     : value.find("visible").and_then(Value::as_bool).unwrap_or(true),
+                    tiles       : tiles,
+                });
+            },
+            Some("group") => {
+                if let Some(children) = value.find("layers").and_then(Value::as_array) {
+                    json_tile_layers(children, layers)?;
+                }
+            },
+            _ => { },
+        }
+    }
+    Ok(())
+}
+
+/// Decodes csv or base64 encoded (optionally zlib or gzip compressed) layer data.
+fn decode(data: &str, encoding: &str, compression: Option<&str>) -> Result<Vec<u32>, Error> {
+    match encoding {
+        "csv" => {
+            data.split(',').map(|gid| gid.trim().parse().map_err(|_| error("invalid tile id"))).collect()
+        },
+        "base64" => {
+            let bytes = data.trim().from_base64().map_err(|error| Error::TilemapError(format!("{}", error)))?;
+            let bytes = match compression {
+                None | Some("") => bytes,
+                Some("zlib") => inflate(ZlibDecoder::new(&bytes[..]))?,
+                Some("gzip") => inflate(GzDecoder::new(&bytes[..])?)?,
+                Some(compression) => return Err(Error::TilemapError(format!("unsupported compression {}", compression))),
+            };
+            Ok(bytes.chunks(4).filter(|gid| gid.len() == 4).map(|gid| {
+                gid[0] as u32 | (gid[1] as u32) << 8 | (gid[2] as u32) << 16 | (gid[3] as u32) << 24
+            }).collect())
+        },
+        _ => Err(Error::TilemapError(format!("unsupported encoding {}", encoding))),
+    }
+}
+
+/// Reads all decompressed data.
+fn inflate<R: Read>(mut decoder: R) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Returns the parsed value of given attribute.
+fn attribute<T: FromStr>(attributes: &[OwnedAttribute], name: &str) -> Option<T> {
+    attributes.iter().find(|attribute| attribute.name.local_name == name).and_then(|attribute| attribute.value.parse().ok())
+}
+
+/// Returns the parsed value of given attribute or an error if it is missing.
+fn required<T: FromStr>(attributes: &[OwnedAttribute], name: &str) -> Result<T, Error> {
+    attribute(attributes, name).ok_or_else(|| Error::TilemapError(format!("missing or invalid attribute {}", name)))
+}
+
+/// Returns the value of a numeric field or an error if it is missing.
+fn json_number(value: &Value, key: &str) -> Result<u32, Error> {
+    value.find(key).and_then(Value::as_u64).map(|number| number as u32).ok_or_else(|| Error::TilemapError(format!("missing or invalid field {}", key)))
+}
+
+fn error(message: &str) -> Error {
+    Error::TilemapError(message.to_string())
+}
@@ -0,0 +1,424 @@
+use prelude::*;
+use image;
+use image::GenericImage;
+use maths::Mat4;
+use core::{layer, Layer, renderer, Renderer, tiled, RenderContext, Sprite, DrawParams, Error};
+use core::tiled::{MapData, TilesetData};
+use std::collections::BTreeMap;
+
+// Width and height of a chunk in tiles.
+const CHUNK_SIZE: u32 = 32;
+
+// Flags stored in the upper bits of Tiled's global tile ids.
+const FLIPPED_HORIZONTALLY: u32 = 0x80000000;
+const FLIPPED_VERTICALLY: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY: u32 = 0x20000000;
+const FLAGS: u32 = 0xF0000000;
+
+/// A tile of a [`Tilemap`](struct.Tilemap.html).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Tile {
+    /// Frame of the tileset sprite.
+    pub frame   : u32,
+    /// Mirrors the tile horizontally.
+    pub flip_x  : bool,
+    /// Mirrors the tile vertically.
+    pub flip_y  : bool,
+}
+
+impl Tile {
+    /// Creates a tile showing given frame of the tileset.
+    pub fn new(frame: u32) -> Tile {
+        Tile {
+            frame   : frame,
+            flip_x  : false,
+            flip_y  : false,
+        }
+    }
+}
+
+/// A grid of tiles drawn from the frames of a tileset [`Sprite`](struct.Sprite.html).
+///
+/// Tilemaps consist of one or more layers of tiles. Each layer is split into chunks of 32x32
+/// tiles which are only redrawn after they were edited, and chunks outside of the view are
+/// skipped. Chunks are created when the first tile is placed in them. Tilemaps are drawn using
+/// [`Renderer::draw_tilemap()`](struct.Renderer.html#method.draw_tilemap).
+///
+/// Tiles larger than the map's tile size are aligned to the bottom left of their cell.
+///
+/// # Tiled
+///
+/// Orthogonal maps created by the Tiled map editor can be loaded from .tmx or .json files using
+/// [`Tilemap::from_file()`](#method.from_file). Embedded and external tilesets are supported as
+/// long as all of them use a single image and the same tile size. Object layers and image layers
+/// are ignored. Diagonally flipped (rotated) tiles are not supported and left empty.
+pub struct Tilemap {
+    tileset     : Sprite,
+    width       : u32,
+    height      : u32,
+    tile_width  : f32,
+    tile_height : f32,
+    layers      : Vec<TileLayer>,
+}
+
+/// A layer of tiles, split into chunks. Chunks are keyed by their row and column.
+struct TileLayer {
+    name        : String,
+    visible     : bool,
+    chunks      : BTreeMap<(u32, u32), Chunk>,
+}
+
+/// A square section of a tile layer and the layer it is drawn on.
+struct Chunk {
+    tiles       : Vec<Option<Tile>>,
+    layer       : Layer,
+    dirty       : AtomicBool,
+}
+
+impl Tilemap {
+
+    /// Creates a new tilemap of width x height tiles using frames of given sprite as tiles.
+    /// The map has no layers initially, see [`Tilemap::add_layer()`](#method.add_layer).
+    pub fn new(tileset: &Sprite, tile_width: f32, tile_height: f32, width: u32, height: u32) -> Tilemap {
+        Tilemap {
+            tileset     : tileset.clone(),
+            width       : width,
+            height      : height,
+            tile_width  : tile_width,
+            tile_height : tile_height,
+            layers      : Vec::new(),
+        }
+    }
+
+    /// Loads a map created by the Tiled map editor from a .tmx or .json file. Tileset images are
+    /// loaded relative to the file referencing them.
+    pub fn from_file(context: &RenderContext, file: &str) -> Tilemap {
+        Self::try_from_file(context, file).unwrap()
+    }
+
+    /// Loads a map created by the Tiled map editor. Returns an error instead of panicking.
+    /// See [`Tilemap::from_file()`](#method.from_file).
+    pub fn try_from_file(context: &RenderContext, file: &str) -> Result<Tilemap, Error> {
+        let path = Path::new(file);
+        let contents = read_file(path)?;
+        let map = if is_json(path) { tiled::parse_json(&contents)? } else { tiled::parse_tmx(&contents)? };
+        create_tilemap(context, path, map)
+    }
+
+    /// Adds a new, empty layer drawn above the existing ones and returns its id.
+    pub fn add_layer(self: &mut Self, name: &str) -> usize {
+        self.layers.push(TileLayer {
+            name        : name.to_string(),
+            visible     : true,
+            chunks      : BTreeMap::new(),
+        });
+        self.layers.len() - 1
+    }
+
+    /// Returns the number of layers.
+    pub fn num_layers(self: &Self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns the id of the first layer with given name.
+    pub fn layer_id(self: &Self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    /// Returns the name of given layer.
+    pub fn layer_name(self: &Self, layer_id: usize) -> &str {
+        &self.layers[layer_id].name
+    }
+
+    /// Shows or hides given layer.
+    pub fn set_layer_visible(self: &mut Self, layer_id: usize, visible: bool) -> &mut Self {
+        self.layers[layer_id].visible = visible;
+        self
+    }
+
+    /// Returns whether given layer is visible.
+    pub fn is_layer_visible(self: &Self, layer_id: usize) -> bool {
+        self.layers[layer_id].visible
+    }
+
+    /// Sets or removes the tile at given tile coordinates of a layer. Coordinates outside of the
+    /// map are ignored.
+    pub fn set_tile(self: &mut Self, layer_id: usize, x: u32, y: u32, tile: Option<Tile>) -> &mut Self {
+        if x < self.width && y < self.height {
+            let (chunk_id, tile_id) = chunk_index(x, y);
+            let chunks = &mut self.layers[layer_id].chunks;
+            if tile.is_none() && !chunks.contains_key(&chunk_id) {
+                return self;
+            }
+            let chunk = chunks.entry(chunk_id).or_insert_with(|| Chunk {
+                tiles       : vec![ None; (CHUNK_SIZE * CHUNK_SIZE) as usize ],
+                layer       : Layer::new(1, 1),
+                dirty       : AtomicBool::new(true),
+            });
+            if chunk.tiles[tile_id] != tile {
+                chunk.tiles[tile_id] = tile;
+                chunk.dirty.store(true, Ordering::Relaxed);
+            }
+        }
+        self
+    }
+
+    /// Returns the tile at given tile coordinates of a layer.
+    pub fn tile(self: &Self, layer_id: usize, x: u32, y: u32) -> Option<Tile> {
+        if x < self.width && y < self.height {
+            let (chunk_id, tile_id) = chunk_index(x, y);
+            self.layers[layer_id].chunks.get(&chunk_id).and_then(|chunk| chunk.tiles[tile_id])
+        } else {
+            None
+        }
+    }
+
+    /// Removes all tiles from given layer.
+    pub fn clear_layer(self: &mut Self, layer_id: usize) -> &mut Self {
+        self.layers[layer_id].chunks.clear();
+        self
+    }
+
+    /// Returns the tile coordinates at given position in pixels, relative to the map's top left
+    /// corner, or None if the position is outside of the map.
+    pub fn tile_at(self: &Self, x: f32, y: f32) -> Option<(u32, u32)> {
+        let (tile_x, tile_y) = ((x / self.tile_width).floor(), (y / self.tile_height).floor());
+        if tile_x >= 0.0 && tile_y >= 0.0 && tile_x < self.width as f32 && tile_y < self.height as f32 {
+            Some((tile_x as u32, tile_y as u32))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the tileset sprite.
+    pub fn tileset(self: &Self) -> &Sprite {
+        &self.tileset
+    }
+
+    /// Returns the width of the map in tiles.
+    pub fn width(self: &Self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the map in tiles.
+    pub fn height(self: &Self) -> u32 {
+        self.height
+    }
+
+    /// Returns the width of a tile in pixels.
+    pub fn tile_width(self: &Self) -> f32 {
+        self.tile_width
+    }
+
+    /// Returns the height of a tile in pixels.
+    pub fn tile_height(self: &Self) -> f32 {
+        self.tile_height
+    }
+}
+
+/// Returns the row and column of the chunk containing given tile and the tile's index within the chunk.
+fn chunk_index(x: u32, y: u32) -> ((u32, u32), usize) {
+    let tile_id = (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE;
+    ((y / CHUNK_SIZE, x / CHUNK_SIZE), tile_id as usize)
+}
+
+/// Draws all visible chunks of the tilemap using the view matrix, blendmode, color and program
+/// of given layer.
+pub fn draw(tilemap: &Tilemap, renderer: &Renderer, layer: &Layer) {
+
+    let view_matrix = renderer::view_matrix(renderer, layer);
+    let chunk_width = CHUNK_SIZE as f32 * tilemap.tile_width;
+    let chunk_height = CHUNK_SIZE as f32 * tilemap.tile_height;
+
+    // tiles larger than a cell extend up and to the right
+    let overhang_x = (tilemap.tileset.width() - tilemap.tile_width).max(0.0);
+    let overhang_y = (tilemap.tileset.height() - tilemap.tile_height).max(0.0);
+
+    for tile_layer in tilemap.layers.iter().filter(|tile_layer| tile_layer.visible) {
+        for (&(row, column), chunk) in tile_layer.chunks.iter() {
+
+            let left = column as f32 * chunk_width;
+            let top = row as f32 * chunk_height;
+
+            if !is_visible(&view_matrix, (left, top - overhang_y), (left + chunk_width + overhang_x, top + chunk_height)) {
+                continue;
+            }
+
+            if chunk.dirty.swap(false, Ordering::Relaxed) {
+                build_chunk(tilemap, chunk, left, top);
+            }
+
+            layer::copy_properties(&chunk.layer, layer);
+            renderer.draw_layer(&chunk.layer);
+        }
+    }
+}
+
+/// Redraws all tiles of a chunk onto its layer.
+fn build_chunk(tilemap: &Tilemap, chunk: &Chunk, left: f32, top: f32) {
+
+    chunk.layer.clear();
+
+    let tileset = &tilemap.tileset;
+
+    for (tile_id, tile) in chunk.tiles.iter().enumerate() {
+        if let Some(tile) = *tile {
+
+            // draw the tile's bottom left corner at the bottom left of its cell, mirrored tiles
+            // extend in the opposite direction and need to be moved back into the cell
+
+            let x = left + (tile_id as u32 % CHUNK_SIZE) as f32 * tilemap.tile_width;
+            let y = top + (tile_id as u32 / CHUNK_SIZE + 1) as f32 * tilemap.tile_height;
+            let x = if tile.flip_x { x + tileset.width() } else { x };
+            let y = if tile.flip_y { y - tileset.height() } else { y };
+
+            tileset.draw_with(&chunk.layer, tile.frame, x, y, &DrawParams {
+                anchor  : Some((0.0, 1.0)),
+                flip_x  : tile.flip_x,
+                flip_y  : tile.flip_y,
+                .. DrawParams::default()
+            });
+        }
+    }
+}
+
+/// Returns whether the given rectangle may be visible within the target.
+fn is_visible(view_matrix: &Mat4<f32>, top_left: (f32, f32), bottom_right: (f32, f32)) -> bool {
+
+    let corners = [
+        view_matrix.transform((top_left.0, top_left.1)),
+        view_matrix.transform((bottom_right.0, top_left.1)),
+        view_matrix.transform((top_left.0, bottom_right.1)),
+        view_matrix.transform((bottom_right.0, bottom_right.1)),
+    ];
+
+    // compare the bounding box of the transformed corners with the target in clip space
+
+    let min_x = corners.iter().fold(f32::INFINITY, |min, corner| min.min(corner.0));
+    let max_x = corners.iter().fold(f32::NEG_INFINITY, |max, corner| max.max(corner.0));
+    let min_y = corners.iter().fold(f32::INFINITY, |min, corner| min.min(corner.1));
+    let max_y = corners.iter().fold(f32::NEG_INFINITY, |max, corner| max.max(corner.1));
+
+    max_x >= -1.0 && min_x <= 1.0 && max_y >= -1.0 && min_y <= 1.0
+}
+
+/// Creates a tilemap from a parsed Tiled map.
+fn create_tilemap(context: &RenderContext, path: &Path, map: MapData) -> Result<Tilemap, Error> {
+
+    // combine the tiles of all tilesets into a single sprite
+
+    let mut data = Vec::new();
+    let mut ranges = Vec::new();
+    let mut num_frames: u32 = 0;
+    let mut tile_size = None;
+
+    for tileset in map.tilesets.iter() {
+
+        let (tileset_path, external) = match tileset.source {
+            Some(ref source) => {
+                let tileset_path = path.with_file_name(source);
+                let contents = read_file(&tileset_path)?;
+                let external = if is_json(&tileset_path) { tiled::parse_tileset_json(&contents)? } else { tiled::parse_tsx(&contents)? };
+                (tileset_path, Some(external))
+            },
+            None => (path.to_path_buf(), None),
+        };
+
+        let tileset_data = external.as_ref().unwrap_or(tileset);
+
+        if *tile_size.get_or_insert((tileset_data.tile_width, tileset_data.tile_height)) != (tileset_data.tile_width, tileset_data.tile_height) {
+            return Err(Error::TilemapError("tilesets with different tile sizes are not supported".to_string()));
+        }
+
+        let count = load_tiles(&tileset_path, tileset_data, &mut data)?;
+        ranges.push((tileset.first_gid, num_frames, count));
+        num_frames = num_frames.checked_add(count).ok_or_else(|| Error::TilemapError("too many tiles".to_string()))?;
+    }
+
+    let (tile_width, tile_height) = match tile_size {
+        Some(tile_size) if num_frames > 0 => tile_size,
+        _ => return Err(Error::TilemapError("map contains no tiles".to_string())),
+    };
+
+    let num_tiles = map.width.checked_mul(map.height).ok_or_else(|| Error::TilemapError(format!("map size {}x{} is too large", map.width, map.height)))?;
+    let tileset = Sprite::try_from_rgba(context, tile_width, tile_height, num_frames, &data)?;
+    let mut tilemap = Tilemap::new(&tileset, map.tile_width as f32, map.tile_height as f32, map.width, map.height);
+
+    // map global tile ids to frames of the combined tileset
+
+    for layer_data in map.layers.iter() {
+        let layer_id = tilemap.add_layer(&layer_data.name);
+        tilemap.set_layer_visible(layer_id, layer_data.visible);
+        for (index, &gid) in layer_data.tiles.iter().enumerate().take(num_tiles as usize) {
+            if gid & FLIPPED_DIAGONALLY != 0 {
+                continue;
+            }
+            let id = gid & !FLAGS;
+            let range = ranges.iter().filter(|range| range.0 <= id).max_by_key(|range| range.0);
+            if let Some(&(first_gid, first_frame, count)) = range {
+                if id - first_gid < count {
+                    tilemap.set_tile(layer_id, index as u32 % map.width, index as u32 / map.width, Some(Tile {
+                        frame   : first_frame + id - first_gid,
+                        flip_x  : gid & FLIPPED_HORIZONTALLY != 0,
+                        flip_y  : gid & FLIPPED_VERTICALLY != 0,
+                    }));
+                }
+            }
+        }
+    }
+
+    Ok(tilemap)
+}
+
+/// Appends the RGBA data of all tiles of a tileset and returns the number of tiles.
+fn load_tiles(path: &Path, tileset: &TilesetData, data: &mut Vec<u8>) -> Result<u32, Error> {
+
+    let image_file = tileset.image.as_ref().ok_or_else(|| Error::TilemapError("tileset without image".to_string()))?;
+    let mut image = image::open(path.with_file_name(image_file))?;
+    let (image_width, image_height) = image.dimensions();
+    let (tile_width, tile_height, margin, spacing) = (tileset.tile_width, tileset.tile_height, tileset.margin, tileset.spacing);
+
+    if tile_width == 0 || tile_height == 0 {
+        return Err(Error::TilemapError("invalid tile size".to_string()));
+    }
+
+    let fit = |size: u32, tile_size: u32| size.saturating_add(spacing).saturating_sub(margin.saturating_mul(2)) / tile_size.saturating_add(spacing);
+    let columns = tileset.columns.unwrap_or_else(|| fit(image_width, tile_width));
+
+    if columns == 0 {
+        return Err(Error::TilemapError(format!("tileset image size {}x{} fits no tiles", image_width, image_height)));
+    }
+
+    let count = match tileset.tile_count {
+        Some(count) => count,
+        None => columns.checked_mul(fit(image_height, tile_height)).ok_or_else(|| Error::TilemapError("too many tiles".to_string()))?,
+    };
+
+    // returns the end of a tile if it lies within the image
+
+    let end = |index: u32, tile_size: u32, image_size: u32| -> Option<u32> {
+        let end = index.checked_mul(tile_size.checked_add(spacing)?)?.checked_add(margin)?.checked_add(tile_size)?;
+        if end <= image_size { Some(end) } else { None }
+    };
+
+    for tile_id in 0..count {
+        match (end(tile_id % columns, tile_width, image_width), end(tile_id / columns, tile_height, image_height)) {
+            (Some(x), Some(y)) => data.extend(image.crop(x - tile_width, y - tile_height, tile_width, tile_height).to_rgba().into_raw()),
+            _ => return Err(Error::TilemapError(format!("tile {} exceeds tileset image size {}x{}", tile_id, image_width, image_height))),
+        }
+    }
+
+    Ok(count)
+}
+
+/// Reads a text file.
+fn read_file(path: &Path) -> Result<String, Error> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Returns whether the file has a .json extension.
+fn is_json(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| extension == "json")
+}
@@ -7,6 +7,10 @@ extern crate rusttype;
 extern crate unicode_normalization;
 extern crate font_loader;
 extern crate avec;
+extern crate serde_json;
+extern crate xml;
+extern crate flate2;
+extern crate rustc_serialize;
 
 mod prelude;
 mod core;
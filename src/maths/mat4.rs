@@ -1,5 +1,6 @@
 use prelude::*;
 use maths::vec3::Vec3;
+use maths::vec2::Vec2;
 use maths::VecType;
 use glium::uniforms::{AsUniformValue, UniformValue};
 
@@ -250,6 +251,34 @@ impl<T: Copy + fmt::Debug + Float + FromPrimitive> Mat4<T> {
         result
     }
 
+    /// Transforms given point by the matrix.
+    pub fn transform<Vector: VecType<T>>(self: &Self, v: Vector) -> Vec3<T> {
+        let a = &self.data;
+        let v = v.as_vec3(T::zero());
+        let w = a[E03] * v.0 + a[E13] * v.1 + a[E23] * v.2 + a[E33];
+        Vec3(
+            (a[E00] * v.0 + a[E10] * v.1 + a[E20] * v.2 + a[E30]) / w,
+            (a[E01] * v.0 + a[E11] * v.1 + a[E21] * v.2 + a[E31]) / w,
+            (a[E02] * v.0 + a[E12] * v.1 + a[E22] * v.2 + a[E32]) / w
+        )
+    }
+
+    /// Returns the point on the z = 0 plane that this matrix transforms to given x and y
+    /// coordinates, or `None` if there is no such single point.
+    pub fn untransform<Vector: VecType<T>>(self: &Self, v: Vector) -> Option<Vec2<T>> {
+        let a = &self.data;
+        let v = v.as_vec3(T::zero());
+        // solve (a0 - v * a3) * p = v * a33 - a3 for the x and y rows
+        let (m00, m01, r0) = (a[E00] - v.0 * a[E03], a[E10] - v.0 * a[E13], v.0 * a[E33] - a[E30]);
+        let (m10, m11, r1) = (a[E01] - v.1 * a[E03], a[E11] - v.1 * a[E13], v.1 * a[E33] - a[E31]);
+        let det = m00 * m11 - m01 * m10;
+        if det == T::zero() {
+            None
+        } else {
+            Some(Vec2((r0 * m11 - m01 * r1) / det, (m00 * r1 - r0 * m10) / det))
+        }
+    }
+
     /// Returns the matrix's translation vector.
     pub fn get_translation(self: &Self) -> Vec3<T> {
         let a = &self.data;
@@ -1,6 +1,7 @@
 use std::time::{Instant, Duration};
 use std::thread;
 
+/// Frame timing information passed to the loop callbacks.
 #[derive(Copy, Clone)]
 pub struct LoopState {
     pub delta       : Duration,
@@ -3,5 +3,5 @@ mod loops;
 mod rng;
 
 pub use avec::AVec;
-pub use misc::loops::{renderloop, mainloop};
+pub use misc::loops::{renderloop, mainloop, LoopState};
 pub use misc::rng::Rng;
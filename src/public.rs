@@ -1,9 +1,9 @@
-pub use core::{BlendMode, blendmodes, Display, DisplayInfo, Monitor, Layer, Renderer, RenderContext, Sprite, Font, FontInfo, Input, ButtonState, Color};
+pub use core::{BlendMode, blendmodes, Display, DisplayInfo, WindowEvent, Monitor, Layer, Mesh, MeshVertex, Tilemap, Tile, Renderer, ScalingPolicy, RenderContext, Sprite, DrawParams, Animation, AnimationPlayer, PlaybackMode, Texture, Program, Uniform, PostProcessor, effects, Font, FontInfo, Input, InputMap, Binding, AxisBinding, InputLog, InputRecorder, InputReplay, ButtonState, MouseButton, TextEvent, Key, Modifiers, GamepadEvent, GamepadBackend, Color, Error};
 pub use maths::{Mat4, Vec2, Vec3, VecType};
 
 pub mod utils {
     //! Optional utility features.
-    pub use misc::{renderloop, mainloop, Rng};
+    pub use misc::{renderloop, mainloop, LoopState, Rng};
 }
 
 pub mod scene {
@@ -1,11 +1,8 @@
 #version 140
 
 uniform sampler2D font_cache;
-uniform sampler2DArray tex1;
-uniform sampler2DArray tex2;
-uniform sampler2DArray tex3;
-uniform sampler2DArray tex4;
-uniform sampler2DArray tex5;
+uniform sampler2DArray atlas;
+uniform sampler2D render_texture;
 uniform vec4 global_color;
 
 in vec2 v_tex_coords;
@@ -24,14 +21,11 @@ void main() {
     if (v_bucket_id == 0u) {
         f_color = texture(font_cache, v_tex_coords).r * color;
     } else if (v_bucket_id == 1u) {
-        f_color = texture(tex1, vec3(v_tex_coords, float(v_texture_id))) * color;
+        vec2 atlas_size = vec2(textureSize(atlas, 0).xy);
+        f_color = texture(atlas, vec3(v_tex_coords / atlas_size, float(v_texture_id))) * color;
     } else if (v_bucket_id == 2u) {
-        f_color = texture(tex2, vec3(v_tex_coords, float(v_texture_id))) * color;
+        f_color = texture(render_texture, v_tex_coords) * color;
     } else if (v_bucket_id == 3u) {
-        f_color = texture(tex3, vec3(v_tex_coords, float(v_texture_id))) * color;
-    } else if (v_bucket_id == 4u) {
-        f_color = texture(tex4, vec3(v_tex_coords, float(v_texture_id))) * color;
-    } else if (v_bucket_id == 5u) {
-        f_color = texture(tex5, vec3(v_tex_coords, float(v_texture_id))) * color;
+        f_color = color;
     }
 }
@@ -0,0 +1,27 @@
+#version 140
+
+uniform sampler2D render_texture;
+uniform sampler2D scene;
+uniform float radius;
+uniform float intensity;
+
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+
+    // vertically blur the horizontally blurred highlights and add them to the scene
+
+    vec2 step = vec2(0.0, radius / 4.0) / vec2(textureSize(render_texture, 0));
+    vec4 glow = texture(render_texture, v_tex_coords) * weights[0];
+
+    for (int i = 1; i < 5; i++) {
+        glow += texture(render_texture, v_tex_coords + step * float(i)) * weights[i];
+        glow += texture(render_texture, v_tex_coords - step * float(i)) * weights[i];
+    }
+
+    f_color = texture(scene, v_tex_coords) + glow * intensity;
+}
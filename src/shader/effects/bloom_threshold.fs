@@ -0,0 +1,18 @@
+#version 140
+
+uniform sampler2D render_texture;
+uniform float threshold;
+
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+void main() {
+
+    // keep only the part of the color that exceeds the brightness threshold
+
+    vec4 color = texture(render_texture, v_tex_coords);
+    float brightness = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+
+    f_color = color * max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+}
@@ -0,0 +1,26 @@
+#version 140
+
+uniform sampler2D render_texture;
+uniform vec2 direction;
+uniform float radius;
+
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+
+    // sample along direction, spreading the taps across the radius
+
+    vec2 step = direction * radius / 4.0 / vec2(textureSize(render_texture, 0));
+    vec4 color = texture(render_texture, v_tex_coords) * weights[0];
+
+    for (int i = 1; i < 5; i++) {
+        color += texture(render_texture, v_tex_coords + step * float(i)) * weights[i];
+        color += texture(render_texture, v_tex_coords - step * float(i)) * weights[i];
+    }
+
+    f_color = color;
+}
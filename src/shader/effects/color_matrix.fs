@@ -0,0 +1,19 @@
+#version 140
+
+uniform sampler2D render_texture;
+uniform mat4 color_matrix;
+
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+void main() {
+
+    // colors are premultiplied, transform the straight color
+
+    vec4 color = texture(render_texture, v_tex_coords);
+    vec3 straight = color.a > 0.0 ? color.rgb / color.a : vec3(0.0);
+    vec4 graded = color_matrix * vec4(straight, 1.0);
+
+    f_color = vec4(clamp(graded.rgb, 0.0, 1.0) * color.a, color.a);
+}
@@ -0,0 +1,31 @@
+#version 140
+
+uniform sampler2D render_texture;
+uniform sampler2D lut;
+
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+void main() {
+
+    // colors are premultiplied, look up the straight color
+
+    vec4 color = texture(render_texture, v_tex_coords);
+    vec3 straight = clamp(color.a > 0.0 ? color.rgb / color.a : vec3(0.0), 0.0, 1.0);
+
+    // the lookup table consists of size slices of size x size pixels, one per blue value.
+    // red increases to the right and green downwards within each slice. textures are stored
+    // bottom-up, so the top row is at v = 1.0
+
+    float size = float(textureSize(lut, 0).y);
+    vec3 scaled = straight * (size - 1.0);
+    float slice0 = floor(scaled.b);
+    float slice1 = min(slice0 + 1.0, size - 1.0);
+    float v = 1.0 - (scaled.g + 0.5) / size;
+
+    vec3 color0 = texture(lut, vec2((slice0 * size + scaled.r + 0.5) / (size * size), v)).rgb;
+    vec3 color1 = texture(lut, vec2((slice1 * size + scaled.r + 0.5) / (size * size), v)).rgb;
+
+    f_color = vec4(mix(color0, color1, scaled.b - slice0) * color.a, color.a);
+}
@@ -0,0 +1,18 @@
+#version 140
+
+uniform sampler2D render_texture;
+uniform float intensity;
+
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+void main() {
+
+    // darken every other row of pixels
+
+    vec4 color = texture(render_texture, v_tex_coords);
+    float line = mod(floor(gl_FragCoord.y), 2.0);
+
+    f_color = vec4(color.rgb * (1.0 - line * intensity), color.a);
+}
@@ -0,0 +1,17 @@
+#version 140
+
+uniform sampler2D render_texture;
+uniform float strength;
+
+in vec2 v_tex_coords;
+
+out vec4 f_color;
+
+void main() {
+
+    vec4 color = texture(render_texture, v_tex_coords);
+    vec2 offset = v_tex_coords - vec2(0.5, 0.5);
+    float vignette = clamp(1.0 - dot(offset, offset) * 2.0 * strength, 0.0, 1.0);
+
+    f_color = vec4(color.rgb * vignette, color.a);
+}